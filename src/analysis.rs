@@ -0,0 +1,277 @@
+use std::fmt;
+use std::sync::Arc;
+
+use crate::disk::Disk;
+use crate::eval::{Evaluator, DISC};
+use crate::game::Game;
+use crate::solvers::alphabeta::AlphaBeta;
+use crate::solvers::table::TranspositionTable;
+use crate::transcript::Transcript;
+
+// Annotates a played game: every move is compared with the best one the searcher
+// can find from the same position, and what it cost decides how it's classified.
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Classification {
+    Best,
+    Inaccuracy,
+    Mistake,
+    Blunder,
+}
+
+impl Classification {
+    pub const ALL: [Classification; 4] = [
+        Classification::Best,
+        Classification::Inaccuracy,
+        Classification::Mistake,
+        Classification::Blunder,
+    ];
+
+    // Losses are in hundredths of a disc: under one disc is as good as best, and
+    // eight or more throws the game away.
+    pub fn from_loss(loss: i32) -> Self {
+        if loss < DISC {
+            Classification::Best
+        } else if loss < 4 * DISC {
+            Classification::Inaccuracy
+        } else if loss < 8 * DISC {
+            Classification::Mistake
+        } else {
+            Classification::Blunder
+        }
+    }
+}
+
+impl fmt::Display for Classification {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Classification::Best => write!(f, "best"),
+            Classification::Inaccuracy => write!(f, "inaccuracy"),
+            Classification::Mistake => write!(f, "mistake"),
+            Classification::Blunder => write!(f, "blunder"),
+        }
+    }
+}
+
+// One move of the game, with scores in hundredths of a disc for the player who made it.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct MoveAnalysis {
+    // counting from 1
+    pub ply: usize,
+    pub player: Disk,
+    pub played: Transcript,
+    pub best: Transcript,
+    pub best_score: i32,
+    pub played_score: i32,
+    pub loss: i32,
+    pub classification: Classification,
+    // true when both scores were solved to the end of the game
+    pub exact: bool,
+    // the opening the game is in after the move, if it's a known one
+    pub opening: Option<&'static str>,
+}
+
+pub struct Analyzer<E: Evaluator> {
+    // searches to its depth, except near the end of the game
+    pub searcher: AlphaBeta<E>,
+    // positions with this many empty squares or fewer are solved exactly
+    pub exact_empties: usize,
+}
+
+impl<E: Evaluator> Analyzer<E> {
+    pub fn new(evaluator: E, depth: usize) -> Self {
+        let mut searcher = AlphaBeta::new(evaluator, depth.max(1));
+        searcher.table = Some(Arc::new(TranspositionTable::new(16)));
+        Self {
+            searcher,
+            exact_empties: 12,
+        }
+    }
+
+    // Every move of the game, from its starting position.
+    pub fn analyze(&mut self, game: &Game) -> Vec<MoveAnalysis> {
+        let mut position = game.restart();
+        let mut analysis = Vec::new();
+        for &played in &game.transcript {
+            analysis.push(self.analyze_move(&position, played));
+            position = position.replay(vec![played]);
+        }
+        analysis
+    }
+
+    fn analyze_move(&mut self, position: &Game, played: Transcript) -> MoveAnalysis {
+        let depth = self.searcher.depth;
+        let empties = position.board().empties();
+        let exact = empties <= self.exact_empties;
+        if exact {
+            self.searcher.depth = empties.max(1);
+        }
+
+        let result = self.searcher.search(position);
+        let best = result.best.unwrap_or(Transcript::Pass);
+        let played_score = if best == played {
+            result.score
+        } else {
+            // the same search the best move was scored with, one ply into it
+            self.searcher.depth -= 1;
+            -self.searcher.search(&position.replay(vec![played])).score
+        };
+        self.searcher.depth = depth;
+
+        let loss = (result.score - played_score).max(0);
+        MoveAnalysis {
+            ply: position.transcript.len() + 1,
+            player: position.turn,
+            played,
+            best,
+            best_score: result.score,
+            played_score,
+            loss,
+            classification: Classification::from_loss(loss),
+            exact,
+            opening: position.replay(vec![played]).opening_name(),
+        }
+    }
+}
+
+fn discs(score: i32) -> f64 {
+    score as f64 / DISC as f64
+}
+
+// The analysis as a table, with each player's mistakes counted up at the end.
+pub fn report(analysis: &[MoveAnalysis]) -> String {
+    let mut out = format!(
+        "{:>4}  {:<6}{:<6}{:<6}{:>7}{:>7}  {:<12}{}\n",
+        "Ply", "Side", "Move", "Best", "Score", "Loss", "Class", "Opening"
+    );
+    for m in analysis {
+        out.push_str(&format!(
+            "{:>4}  {:<6}{:<6}{:<6}{:>+7.2}{:>7.2}  {:<12}{}\n",
+            m.ply,
+            m.player.to_string(),
+            m.played.to_string(),
+            m.best.to_string(),
+            discs(m.played_score),
+            discs(m.loss),
+            m.classification.to_string(),
+            m.opening.unwrap_or("")
+        ));
+    }
+
+    for player in [Disk::Dark, Disk::Light] {
+        let moves: Vec<&MoveAnalysis> = analysis.iter().filter(|m| m.player == player).collect();
+        let counts: Vec<String> = Classification::ALL[1..]
+            .iter()
+            .map(|c| {
+                let count = moves.iter().filter(|m| m.classification == *c).count();
+                match (count, c) {
+                    (1, _) => format!("1 {}", c),
+                    (_, Classification::Inaccuracy) => format!("{} inaccuracies", count),
+                    _ => format!("{} {}s", count, c),
+                }
+            })
+            .collect();
+        let lost: i32 = moves.iter().map(|m| m.loss).sum();
+        let average = if moves.is_empty() { 0.0 } else { discs(lost) / moves.len() as f64 };
+        out.push_str(&format!("\n{}: {}; average loss {:.2}", player, counts.join(", "), average));
+    }
+    out.push('\n');
+    out
+}
+
+// The analysis as JSON, with scores in discs.
+pub fn to_json(game: &Game, analysis: &[MoveAnalysis]) -> String {
+    let string = |s: &str| format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\""));
+    let moves: Vec<String> = analysis
+        .iter()
+        .map(|m| {
+            format!(
+                "{{\"ply\": {}, \"player\": \"{}\", \"move\": \"{}\", \"best\": \"{}\", \"score\": {:.2}, \"best_score\": {:.2}, \"loss\": {:.2}, \"classification\": \"{}\", \"exact\": {}, \"opening\": {}}}",
+                m.ply,
+                m.player,
+                m.played,
+                m.best,
+                discs(m.played_score),
+                discs(m.best_score),
+                discs(m.loss),
+                m.classification,
+                m.exact,
+                m.opening.map_or(String::from("null"), string)
+            )
+        })
+        .collect();
+    let result = game.result().map_or(String::from("null"), |r| string(&r.to_string()));
+
+    format!(
+        "{{\"transcript\": \"{}\", \"result\": {}, \"moves\": [\n  {}\n]}}\n",
+        Transcript::stringify(&game.transcript),
+        result,
+        moves.join(",\n  ")
+    )
+}
+
+// Evaluations of each move in discs, for the player who made it, as annotated
+// GGF records carry them.
+pub fn evaluations(analysis: &[MoveAnalysis]) -> Vec<Option<f64>> {
+    analysis.iter().map(|m| Some(discs(m.played_score))).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::eval::heuristic::{Heuristic, Parity};
+    use crate::solvers::random::{Random, Seed};
+    use crate::transcript::MANUBU_MARUO;
+
+    #[test]
+    fn classifications() {
+        assert_eq!(Classification::from_loss(0), Classification::Best);
+        assert_eq!(Classification::from_loss(DISC - 1), Classification::Best);
+        assert_eq!(Classification::from_loss(3 * DISC), Classification::Inaccuracy);
+        assert_eq!(Classification::from_loss(4 * DISC), Classification::Mistake);
+        assert_eq!(Classification::from_loss(20 * DISC), Classification::Blunder);
+    }
+
+    #[test]
+    fn finds_blunders() {
+        // light's last move lets dark wipe it out, which a two ply search sees coming
+        let game = Game::from_transcript(Transcript::from_string(MANUBU_MARUO));
+        let analysis = Analyzer::new(Parity, 2).analyze(&game);
+        assert_eq!(analysis.len(), game.transcript.len());
+
+        let last = analysis.last().unwrap();
+        assert_eq!(last.player, Disk::Dark);
+        assert_eq!(last.played, Transcript::from_string("C5")[0]);
+        assert_eq!(last.classification, Classification::Best);
+
+        let blunder = &analysis[analysis.len() - 2];
+        assert_eq!(blunder.player, Disk::Light);
+        assert_eq!(blunder.classification, Classification::Blunder);
+        assert_ne!(blunder.best, blunder.played);
+        assert!(blunder.loss >= 8 * DISC);
+
+        assert_eq!(analysis[0].opening, crate::openings::name(&game.transcript[..1]));
+        let text = report(&analysis);
+        assert!(text.contains("   8  Light F5    G3     -13.00   8.00  blunder     Perpendicular\n"));
+        assert!(text.contains("Light: 0 inaccuracies, 1 mistake, 1 blunder; average loss 3.00"));
+        let json = to_json(&game, &analysis);
+        assert!(json.contains("\"classification\": \"blunder\""));
+        assert!(json.contains("\"result\": \"Dark wins 64-0\""));
+    }
+
+    #[test]
+    fn exact_near_the_end() {
+        // a small board is solved exactly from the start
+        let game = Random::new(Game::with_size(4), Seed::from_string("analysis".to_string())).next().unwrap();
+        let mut analyzer = Analyzer::new(Heuristic::new(), 2);
+        let analysis = analyzer.analyze(&game);
+        assert!(analysis.iter().all(|m| m.exact && m.loss % DISC == 0));
+
+        // a move that was the best keeps its full score
+        for m in analysis.iter().filter(|m| m.played == m.best) {
+            assert_eq!(m.played_score, m.best_score);
+        }
+    }
+}
@@ -1,20 +1,47 @@
-use crate::position::{PositionIter, PositionState};
+use std::collections::HashSet;
 
-pub const MAX_X: usize = 7;
-pub const MAX_Y: usize = 7;
+use crate::direction::Direction;
+use crate::disk::Disk;
+use crate::position::{Position, PositionIter, PositionState};
+
+// the standard board
+pub const SIZE: usize = 8;
+pub const MAX_X: usize = SIZE - 1;
+pub const MAX_Y: usize = SIZE - 1;
+
+// Other sizes are played too, from 4x4 up to 16x16; the largest fits two letters
+// of transcript notation (A..P) and keeps the board cheap to copy.
+pub const MIN_SIZE: usize = 4;
+pub const MAX_SIZE: usize = 16;
 
 #[derive(Clone, Copy, Debug, PartialEq)]
 pub struct Board {
-    data: [[PositionState; MAX_Y + 1]; MAX_X + 1],
+    size: usize,
+    data: [[PositionState; MAX_SIZE]; MAX_SIZE],
 }
 
 impl Board {
     pub fn empty() -> Self {
+        Board::with_size(SIZE)
+    }
+
+    // an empty board `size` squares across
+    pub fn with_size(size: usize) -> Self {
+        assert!((MIN_SIZE..=MAX_SIZE).contains(&size), "board size must be between {} and {}", MIN_SIZE, MAX_SIZE);
         Board {
-            data: [[PositionState::Empty; MAX_Y + 1]; MAX_X + 1],
+            size,
+            data: [[PositionState::Empty; MAX_SIZE]; MAX_SIZE],
         }
     }
 
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    pub fn squares(&self) -> usize {
+        self.size * self.size
+    }
+
     pub fn get(&self, x: usize, y: usize) -> PositionState {
         self.data[x][y]
     }
@@ -31,22 +58,130 @@ impl Board {
 
     // returns an iterator of Positions; not strictly necessary, just here for convenience.
     pub fn iter(&self) -> PositionIter {
-        PositionIter::new()
+        PositionIter::with_size(self.size)
     }
 
+    pub fn empties(&self) -> usize {
+        self.iter().filter(|p| self.get(p.x, p.y) == PositionState::Empty).count()
+    }
+
+    // Discs of the given color that can never be flipped. A disc is stable when,
+    // along each of the four lines through it, either the whole line is filled or
+    // one side is the board's edge, a blocked square or another stable disc of the
+    // same color. That is repeated until nothing changes, growing inward from the corners.
+    pub fn stable_discs(&self, disk: Disk) -> HashSet<Position> {
+        let state: PositionState = disk.into();
+        let mut stable = HashSet::new();
+
+        loop {
+            let mut changed = false;
+
+            for p in self.iter() {
+                if self.get(p.x, p.y) != state || stable.contains(&p) {
+                    continue;
+                }
+
+                let anchored = |d: &Direction| match p.neighbor(d, self.size) {
+                    None => true,
+                    Some(n) => self.get(n.x, n.y) == PositionState::Blocked || stable.contains(&n),
+                };
+
+                let is_stable = Direction::AXES
+                    .iter()
+                    .all(|(a, b)| anchored(a) || anchored(b) || self.is_filled(p, a, b));
+
+                if is_stable {
+                    stable.insert(p);
+                    changed = true;
+                }
+            }
+
+            if !changed {
+                return stable;
+            }
+        }
+    }
+
+    // true if there are no empty squares on the line through `p`, which ends at
+    // the edges of the board or at blocked squares
+    fn is_filled(&self, p: Position, a: &Direction, b: &Direction) -> bool {
+        [a, b].iter().all(|d| {
+            let mut current = p.neighbor(d, self.size);
+            while let Some(n) = current {
+                match self.get(n.x, n.y) {
+                    PositionState::Empty => return false,
+                    PositionState::Blocked => return true,
+                    _ => (),
+                }
+                current = n.neighbor(d, self.size);
+            }
+            true
+        })
+    }
+
+    // The board as rows from the top, with `.` for empty squares, `D` and `L` for
+    // discs and `#` for blocked squares.
+    pub fn rows(&self) -> Vec<String> {
+        (0..self.size)
+            .map(|y| {
+                (0..self.size)
+                    .map(|x| match self.get(x, y) {
+                        PositionState::Empty => '.',
+                        PositionState::Dark => 'D',
+                        PositionState::Light => 'L',
+                        PositionState::Blocked => '#',
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    // Reads rows written by `rows`; there have to be as many as each is long.
+    pub fn from_rows<S: AsRef<str>>(rows: &[S]) -> Option<Board> {
+        if !(MIN_SIZE..=MAX_SIZE).contains(&rows.len()) {
+            return None;
+        }
+
+        let mut board = Board::with_size(rows.len());
+        for (y, row) in rows.iter().enumerate() {
+            let row = row.as_ref();
+            if row.chars().count() != rows.len() {
+                return None;
+            }
+            for (x, c) in row.chars().enumerate() {
+                let state = match c {
+                    '.' => PositionState::Empty,
+                    'D' => PositionState::Dark,
+                    'L' => PositionState::Light,
+                    '#' => PositionState::Blocked,
+                    _ => return None,
+                };
+                board.set(x, y, state);
+            }
+        }
+        Some(board)
+    }
 }
 
 impl std::fmt::Display for Board {
+    #[allow(clippy::single_char_add_str)]
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
         let mut output = String::new();
-        output.push_str("  a b c d e f g h");
-        for y in 0..=MAX_Y {
-            output.push_str(&format!("\n{}", y + 1));
-            for x in 0..=MAX_X {
+        // row numbers are padded once they reach two digits
+        let width = self.size.to_string().len();
+        output.push_str(&" ".repeat(width));
+        for x in 0..self.size {
+            output.push(' ');
+            output.push((b'a' + x as u8) as char);
+        }
+        for y in 0..self.size {
+            output.push_str(&format!("\n{:>width$}", y + 1, width = width));
+            for x in 0..self.size {
                 match self.get(x, y) {
                     PositionState::Empty => output.push_str(" •"),
                     PositionState::Dark => output.push_str(" D"),
                     PositionState::Light => output.push_str(" L"),
+                    PositionState::Blocked => output.push_str(" #"),
                 };
             }
         }
@@ -83,4 +218,80 @@ mod tests {
         assert_eq!(b.get(0, 1), PositionState::Empty);
     }
 
+    #[test]
+    fn stable_discs() {
+        let mut b = Board::empty();
+
+        // a corner and its unbroken edge run are stable, a detached edge disc isn't
+        for x in 0..3 {
+            b.set(x, 0, PositionState::Dark);
+        }
+        b.set(5, 0, PositionState::Dark);
+        assert_eq!(b.stable_discs(Disk::Dark).len(), 3);
+
+        // B2 is exposed along the second row until A2 is filled in
+        b.set(1, 1, PositionState::Dark);
+        assert_eq!(b.stable_discs(Disk::Dark).len(), 3);
+        b.set(0, 1, PositionState::Dark);
+        assert_eq!(b.stable_discs(Disk::Dark).len(), 5);
+        assert!(b.stable_discs(Disk::Dark).contains(&Position { x: 1, y: 1 }));
+        assert!(b.stable_discs(Disk::Light).is_empty());
+
+        // a light disc wedged into a completely filled board can't be flipped either
+        let mut full = Board::empty();
+        for p in full.iter() {
+            full.set(p.x, p.y, PositionState::Dark);
+        }
+        full.set(4, 4, PositionState::Light);
+        assert_eq!(full.stable_discs(Disk::Light).len(), 1);
+        assert_eq!(full.stable_discs(Disk::Dark).len(), 63);
+
+        // nothing is stable at the start
+        let mut start = Board::empty();
+        start.set(3, 3, PositionState::Light);
+        start.set(4, 4, PositionState::Dark);
+        assert!(start.stable_discs(Disk::Dark).is_empty());
+    }
+
+    #[test]
+    fn sizes() {
+        let mut b = Board::with_size(6);
+        assert_eq!(b.squares(), 36);
+        assert_eq!(b.iter().count(), 36);
+
+        // the edges move in with the board
+        b.set(5, 5, PositionState::Dark);
+        assert_eq!(b.stable_discs(Disk::Dark).len(), 1);
+        assert!(b.to_string().starts_with("  a b c d e f\n1"));
+
+        assert!(Board::with_size(10).to_string().contains("\n10 •"));
+    }
+
+    #[test]
+    fn blocked_squares() {
+        // a blocked square anchors a disc next to it like the edge of the board does
+        let mut b = Board::empty();
+        b.set(4, 0, PositionState::Dark);
+        assert!(b.stable_discs(Disk::Dark).is_empty());
+        b.set(3, 0, PositionState::Blocked);
+        assert_eq!(b.stable_discs(Disk::Dark).len(), 1);
+
+        // and a line stops at it
+        for (x, state) in [PositionState::Blocked, PositionState::Light, PositionState::Dark, PositionState::Light].iter().enumerate() {
+            b.set(x, 2, *state);
+        }
+        let (east, west) = &Direction::AXES[0];
+        assert!(!b.is_filled(Position { x: 2, y: 2 }, east, west));
+        b.set(4, 2, PositionState::Blocked);
+        assert!(b.is_filled(Position { x: 2, y: 2 }, east, west));
+        assert_eq!(b.empties(), 57);
+
+        let rows = b.rows();
+        assert_eq!(rows[0], "...#D...");
+        assert_eq!(rows[2], "#LDL#...");
+        assert_eq!(Board::from_rows(&rows), Some(b));
+        assert_eq!(Board::from_rows(&["...", "...", "..."]), None);
+        assert_eq!(Board::from_rows(&["....", "..x.", "....", "...."]), None);
+    }
+
 }
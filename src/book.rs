@@ -0,0 +1,470 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::Path;
+
+use rand::Rng;
+
+use crate::board::SIZE;
+use crate::disk::Disk;
+use crate::eval::{final_score, Evaluator};
+use crate::game::Game;
+use crate::position::{Position, PositionIter, PositionState};
+use crate::solvers::alphabeta::AlphaBeta;
+use crate::transcript::Transcript;
+
+const MAGIC: &[u8; 4] = b"RVBK";
+const VERSION: u32 = 1;
+
+// Positions are keyed by the smallest of their eight symmetrical images, packed two
+// bits per square, along with the side to move. Books only cover the standard board.
+type Key = (u128, Disk);
+
+// true for games a book can hold: standard rules on the standard board, from the standard layout
+fn covers(game: &Game) -> bool {
+    game.board().size() == SIZE && game.rules().is_standard() && game.has_standard_layout()
+}
+
+pub fn key(game: &Game) -> Key {
+    let board = game.board();
+    assert_eq!(board.size(), SIZE, "books only cover the standard board");
+    let squares = Position::SYMMETRIES
+        .iter()
+        .map(|f| {
+            PositionIter::new().fold(0u128, |packed, p| {
+                let bits = match board.get(p.x, p.y) {
+                    PositionState::Empty => 0,
+                    PositionState::Dark => 1,
+                    PositionState::Light => 2,
+                    PositionState::Blocked => 3,
+                };
+                let image = f(p, SIZE);
+                packed | bits << (2 * (image.y * 8 + image.x))
+            })
+        })
+        .min()
+        .unwrap();
+
+    (squares, game.turn)
+}
+
+// What the book knows about one position. Results and scores are from the point of
+// view of the side to move there.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Node {
+    pub wins: u32,
+    pub draws: u32,
+    pub losses: u32,
+    // static search score, set on leaves by `evaluate`
+    pub eval: Option<i32>,
+    // minimax of the evaluations below this position, set by `propagate`
+    pub value: Option<i32>,
+}
+
+impl Node {
+    pub fn games(&self) -> u32 {
+        self.wins + self.draws + self.losses
+    }
+
+    // the average result, counting a win as 1 and a draw as 1/2
+    pub fn score(&self) -> f64 {
+        if self.games() == 0 {
+            return 0.5;
+        }
+        (self.wins as f64 + self.draws as f64 / 2.0) / self.games() as f64
+    }
+
+    // the same position seen from the other side
+    fn reversed(&self) -> Node {
+        Node {
+            wins: self.losses,
+            draws: self.draws,
+            losses: self.wins,
+            eval: self.eval.map(|e| -e),
+            value: self.value.map(|v| -v),
+        }
+    }
+}
+
+// A book move, with everything from the point of view of the player making it.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Candidate {
+    pub play: Transcript,
+    pub node: Node,
+    // the chance of choosing this move; the candidates' weights add up to one
+    pub weight: f64,
+}
+
+pub struct Book {
+    // moves valued within this much (in hundredths of a disc) of the best are all played
+    pub margin: i32,
+    nodes: HashMap<Key, Node>,
+}
+
+impl Book {
+    pub fn new() -> Self {
+        Self {
+            margin: 200,
+            nodes: HashMap::new(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    pub fn get(&self, game: &Game) -> Option<&Node> {
+        if !covers(game) {
+            return None;
+        }
+        self.nodes.get(&key(game))
+    }
+
+    // Adds the positions of the game's first `plies` moves. Complete games also count
+    // towards the win, draw and loss records of every one of them. Games on other
+    // board sizes or under other rules are left out.
+    pub fn add_game(&mut self, game: &Game, plies: usize) {
+        if !covers(game) {
+            return;
+        }
+        let outcome = game.result().map(|r| r.outcome(Disk::Dark));
+
+        let mut position = Game::new();
+        for ply in 0..=plies.min(game.transcript.len()) {
+            if ply > 0 {
+                position = position.apply(game.transcript[ply - 1]).expect("game has an invalid move");
+            }
+
+            let node = self.nodes.entry(key(&position)).or_default();
+            let outcome = match position.turn {
+                Disk::Dark => outcome,
+                Disk::Light => outcome.map(|o| -o),
+            };
+            match outcome {
+                Some(1) => node.wins += 1,
+                Some(0) => node.draws += 1,
+                Some(_) => node.losses += 1,
+                None => (),
+            }
+        }
+    }
+
+    // The moves from this position that lead back into the book.
+    fn children(&self, game: &Game) -> Vec<(Transcript, Game)> {
+        if !covers(game) {
+            return Vec::new();
+        }
+        game.legal_actions()
+            .into_iter()
+            .map(|t| (t, game.apply(t).expect("legal actions can be applied")))
+            .filter(|(_, child)| self.nodes.contains_key(&key(child)))
+            .collect()
+    }
+
+    // One game reaching each position in the book, found breadth first from the start.
+    fn positions(&self) -> Vec<Game> {
+        let mut seen = HashSet::new();
+        let mut queue = VecDeque::new();
+        let mut positions = Vec::new();
+
+        let start = Game::new();
+        if self.nodes.contains_key(&key(&start)) {
+            seen.insert(key(&start));
+            queue.push_back(start);
+        }
+
+        while let Some(game) = queue.pop_front() {
+            for (_, child) in self.children(&game) {
+                if seen.insert(key(&child)) {
+                    queue.push_back(child);
+                }
+            }
+            positions.push(game);
+        }
+
+        positions
+    }
+
+    // Searches every leaf of the book that hasn't been evaluated yet, returning how
+    // many were.
+    pub fn evaluate<E: Evaluator>(&mut self, searcher: &mut AlphaBeta<E>) -> usize {
+        let leaves: Vec<Game> = self
+            .positions()
+            .into_iter()
+            .filter(|g| self.nodes[&key(g)].eval.is_none() && self.children(g).is_empty())
+            .collect();
+
+        for game in &leaves {
+            let score = if game.is_complete() {
+                final_score(game, game.turn)
+            } else {
+                searcher.search(game).score
+            };
+            self.nodes.get_mut(&key(game)).unwrap().eval = Some(score);
+        }
+
+        leaves.len()
+    }
+
+    // Backs the leaf evaluations up the book by negamax, so every position's value is
+    // the score of the best line through the book from there.
+    pub fn propagate(&mut self) {
+        let mut done = HashSet::new();
+        self.propagate_from(&Game::new(), &mut done);
+    }
+
+    fn propagate_from(&mut self, game: &Game, done: &mut HashSet<Key>) -> Option<i32> {
+        let key = key(game);
+        let node = *self.nodes.get(&key)?;
+        if !done.insert(key) {
+            return node.value;
+        }
+
+        let best = self
+            .children(game)
+            .iter()
+            .filter_map(|(_, child)| self.propagate_from(child, done))
+            .map(|v| -v)
+            .max();
+
+        let value = best.or(node.eval);
+        self.nodes.get_mut(&key).unwrap().value = value;
+        value
+    }
+
+    // The book moves in this position and how often to play each. When moves have
+    // values only those within `margin` of the best are played, otherwise any move
+    // might be; either way, in proportion to how often they've been played before.
+    pub fn lookup(&self, game: &Game) -> Vec<Candidate> {
+        let mut candidates: Vec<Candidate> = self
+            .children(game)
+            .into_iter()
+            .map(|(play, child)| Candidate {
+                play,
+                node: self.nodes[&key(&child)].reversed(),
+                weight: 0.0,
+            })
+            .collect();
+
+        let best = candidates.iter().filter_map(|c| c.node.value).max();
+        for c in candidates.iter_mut() {
+            let playable = match (best, c.node.value) {
+                (Some(best), Some(value)) => value >= best - self.margin,
+                (Some(_), None) => false,
+                (None, _) => true,
+            };
+            if playable {
+                c.weight = c.node.games() as f64 + 1.0;
+            }
+        }
+
+        let total: f64 = candidates.iter().map(|c| c.weight).sum();
+        for c in candidates.iter_mut() {
+            c.weight /= total;
+        }
+
+        candidates.sort_by(|a, b| b.weight.partial_cmp(&a.weight).unwrap());
+        candidates
+    }
+
+    // A book move picked at random by weight, if there are any.
+    pub fn choose<R: Rng>(&self, game: &Game, rng: &mut R) -> Option<Transcript> {
+        let candidates = self.lookup(game);
+        let mut target: f64 = rng.gen();
+        for c in &candidates {
+            if target < c.weight {
+                return Some(c.play);
+            }
+            target -= c.weight;
+        }
+        candidates.first().map(|c| c.play)
+    }
+
+    // Book files are little-endian: the magic bytes, the format version, the number of
+    // positions, and then each position's key, results, evaluation and value. Missing
+    // scores are written as i32::MIN.
+    pub fn load<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let mut reader = BufReader::new(File::open(path)?);
+        let invalid = |message: &str| io::Error::new(io::ErrorKind::InvalidData, message.to_string());
+
+        let mut magic = [0u8; 4];
+        reader.read_exact(&mut magic)?;
+        if &magic != MAGIC {
+            return Err(invalid("not an opening book"));
+        }
+        if read_u32(&mut reader)? != VERSION {
+            return Err(invalid("unsupported opening book version"));
+        }
+
+        let mut book = Book::new();
+        for _ in 0..read_u32(&mut reader)? {
+            let mut squares = [0u8; 16];
+            reader.read_exact(&mut squares)?;
+            let mut turn = [0u8; 1];
+            reader.read_exact(&mut turn)?;
+            let turn = match turn[0] {
+                0 => Disk::Dark,
+                1 => Disk::Light,
+                _ => return Err(invalid("invalid side to move in opening book")),
+            };
+
+            let score = |raw: u32| match raw as i32 {
+                i32::MIN => None,
+                s => Some(s),
+            };
+            let node = Node {
+                wins: read_u32(&mut reader)?,
+                draws: read_u32(&mut reader)?,
+                losses: read_u32(&mut reader)?,
+                eval: score(read_u32(&mut reader)?),
+                value: score(read_u32(&mut reader)?),
+            };
+            book.nodes.insert((u128::from_le_bytes(squares), turn), node);
+        }
+
+        Ok(book)
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        let mut writer = BufWriter::new(File::create(path)?);
+
+        writer.write_all(MAGIC)?;
+        writer.write_all(&VERSION.to_le_bytes())?;
+        writer.write_all(&(self.nodes.len() as u32).to_le_bytes())?;
+
+        for ((squares, turn), node) in &self.nodes {
+            writer.write_all(&squares.to_le_bytes())?;
+            writer.write_all(&[match turn {
+                Disk::Dark => 0,
+                Disk::Light => 1,
+            }])?;
+            for n in &[node.wins, node.draws, node.losses] {
+                writer.write_all(&n.to_le_bytes())?;
+            }
+            for score in &[node.eval, node.value] {
+                writer.write_all(&score.unwrap_or(i32::MIN).to_le_bytes())?;
+            }
+        }
+
+        writer.flush()
+    }
+}
+
+impl Default for Book {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn read_u32<R: Read>(reader: &mut R) -> io::Result<u32> {
+    let mut bytes = [0u8; 4];
+    reader.read_exact(&mut bytes)?;
+    Ok(u32::from_le_bytes(bytes))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::eval::heuristic::Heuristic;
+    use crate::eval::DISC;
+    use crate::solvers::random::{Random, Seed};
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    fn random_games(count: usize) -> Vec<Game> {
+        (0..count)
+            .map(|i| Random::new(Game::new(), Seed::from_string(format!("book {}", i))).next().unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn symmetrical_keys() {
+        let transcript = Transcript::from_string("F5D6C3D3C4F4F6F3E6E7");
+        let expected = key(&Game::from_transcript(transcript.clone()));
+
+        for t in Transcript::symmetrical(transcript, SIZE) {
+            assert_eq!(key(&Game::from_transcript(t)), expected);
+        }
+
+        // all four first moves are the same opening
+        let openings: HashSet<Key> = Game::new().valid_moves().into_iter().map(|vm| key(&Game::new().play(vm))).collect();
+        assert_eq!(openings.len(), 1);
+    }
+
+    #[test]
+    fn records_results() {
+        let mut book = Book::new();
+        let games = random_games(20);
+        for g in &games {
+            book.add_game(g, 4);
+        }
+
+        let start = book.get(&Game::new()).unwrap();
+        assert_eq!(start.games(), 20);
+
+        let dark_wins = games.iter().filter(|g| final_score(g, Disk::Dark) > 0).count();
+        assert_eq!(start.wins as usize, dark_wins);
+
+        // every first move is the same position under symmetry
+        let first = Game::from_transcript(games[0].transcript[..1].to_vec());
+        assert_eq!(book.get(&first).unwrap().losses, start.wins);
+
+        let candidates = book.lookup(&Game::new());
+        assert_eq!(candidates.len(), 4);
+        assert!((candidates.iter().map(|c| c.weight).sum::<f64>() - 1.0).abs() < 1e-9);
+        assert!(book.lookup(&games[0]).is_empty());
+    }
+
+    #[test]
+    fn propagates_values() {
+        let mut book = Book::new();
+        for g in random_games(30) {
+            book.add_game(&g, 3);
+        }
+
+        let mut searcher = AlphaBeta::new(Heuristic::new(), 2);
+        let leaves = book.evaluate(&mut searcher);
+        assert!(leaves > 0);
+        assert_eq!(book.evaluate(&mut searcher), 0);
+        book.propagate();
+
+        // the value of a position is the best of its children's
+        let game = Game::new().play(Game::new().valid_moves().remove(0));
+        let candidates = book.lookup(&game);
+        let best = candidates.iter().filter_map(|c| c.node.value).max().unwrap();
+        assert_eq!(book.get(&game).unwrap().value, Some(best));
+
+        // only moves near the best are played
+        for c in &candidates {
+            let near = c.node.value.unwrap() >= best - book.margin;
+            assert_eq!(c.weight > 0.0, near);
+        }
+
+        let mut rng = StdRng::seed_from_u64(1);
+        let choice = book.choose(&game, &mut rng).unwrap();
+        let chosen = candidates.iter().find(|c| c.play == choice).unwrap();
+        assert!(chosen.node.value.unwrap() >= best - 2 * DISC);
+    }
+
+    #[test]
+    fn save_and_load() {
+        let mut book = Book::new();
+        for g in random_games(10) {
+            book.add_game(&g, 6);
+        }
+        book.evaluate(&mut AlphaBeta::new(Heuristic::new(), 1));
+        book.propagate();
+
+        let path = std::env::temp_dir().join(format!("reversi-book-{}.bin", std::process::id()));
+        book.save(&path).unwrap();
+        let loaded = Book::load(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(loaded.nodes, book.nodes);
+        assert!(Book::load("/nonexistent/book.bin").is_err());
+    }
+}
@@ -0,0 +1,69 @@
+use std::time::Duration;
+
+use crate::game::Game;
+
+// A player's game clock, with an optional increment added after every move.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Clock {
+    pub remaining: Duration,
+    pub increment: Duration,
+}
+
+impl Clock {
+    pub fn new(remaining: Duration, increment: Duration) -> Self {
+        Clock { remaining, increment }
+    }
+
+    // How long to spend on the next move: an even share of the remaining time
+    // over the moves we still expect to make, plus most of the increment.
+    pub fn budget(&self, game: &Game) -> Duration {
+        let own_moves_left = game.board().empties().div_ceil(2) as u32;
+
+        let share = self.remaining / (own_moves_left + 1);
+        let budget = share + self.increment * 3 / 4;
+
+        // never risk more than half of what's left on the clock
+        budget.min(self.remaining / 2)
+    }
+
+    // Charges a move to the clock. Returns false if the time ran out.
+    pub fn charge(&mut self, elapsed: Duration) -> bool {
+        match self.remaining.checked_sub(elapsed) {
+            Some(remaining) => {
+                self.remaining = remaining + self.increment;
+                true
+            }
+            None => {
+                self.remaining = Duration::from_secs(0);
+                false
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn budget() {
+        let clock = Clock::new(Duration::from_secs(60), Duration::from_secs(0));
+        let budget = clock.budget(&Game::new());
+
+        // 30 moves to make from the start
+        assert_eq!(budget, Duration::from_secs(60) / 31);
+
+        let short = Clock::new(Duration::from_secs(1), Duration::from_secs(10));
+        assert_eq!(short.budget(&Game::new()), Duration::from_millis(500));
+    }
+
+    #[test]
+    fn charge() {
+        let mut clock = Clock::new(Duration::from_secs(10), Duration::from_secs(1));
+        assert!(clock.charge(Duration::from_secs(4)));
+        assert_eq!(clock.remaining, Duration::from_secs(7));
+
+        assert!(!clock.charge(Duration::from_secs(8)));
+        assert_eq!(clock.remaining, Duration::from_secs(0));
+    }
+}
@@ -4,6 +4,7 @@ use std::time::Instant;
 
 use num_format::{Locale, ToFormattedString};
 
+use crate::game::Game;
 use crate::transcript::Transcript;
 
 pub trait Collector {
@@ -23,6 +24,12 @@ impl Printer {
     }
 }
 
+impl Default for Printer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Collector for Printer {
     fn sender(&self) -> Sender<Vec<Transcript>> {
         self._sender.clone()
@@ -53,12 +60,19 @@ impl Counter {
     }
 }
 
+impl Default for Counter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Collector for Counter {
 
     fn sender(&self) -> Sender<Vec<Transcript>> {
         self._sender.clone()
     }
 
+    #[allow(clippy::manual_is_multiple_of)]
     fn start(&mut self) {
         let timer = Instant::now();
         loop {
@@ -77,7 +91,10 @@ impl Collector for Counter {
                             per_game, 
                             per_second,
                         );
-                        println!(" => {}", Transcript::stringify(&t));
+                        // the sample game's result; replaying every game would slow counting down
+                        let result = Game::from_transcript(t.clone()).result();
+                        let result = result.map_or(String::from("unfinished"), |r| r.to_string());
+                        println!(" => {} ({})", Transcript::stringify(&t), result);
                     }
                 },
                 Err(e) => panic!("{}", e),
@@ -1,47 +1,279 @@
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use rand::rngs::StdRng;
+
+use crate::book::Book;
+use crate::clock::Clock;
 use crate::disk::Disk;
+use crate::eval::Evaluator;
 use crate::game::Game;
+use crate::result::GameResult;
+use crate::solvers::deepening::Deepening;
+use crate::solvers::random::Seed;
+use crate::solvers::table::TranspositionTable;
 
 pub struct Contest {
     pub game: Game,
-    dark: Player,
-    light: Player,
+    pub dark_clock: Option<Clock>,
+    pub light_clock: Option<Clock>,
+    // set if a player ran out of time or didn't make a legal move, ending the contest early
+    pub forfeit: Option<Disk>,
+    pub forfeit_reason: Option<String>,
+    dark: Box<dyn Player>,
+    light: Box<dyn Player>,
 }
 
 impl Contest {
-    pub fn new(game: Game, mut dark: Player, mut light: Player) -> Self {
-        dark.disk = Disk::Dark;
-        light.disk = Disk::Light;
-        Self { game, dark, light }
+    pub fn new(game: Game, dark: Box<dyn Player>, light: Box<dyn Player>) -> Self {
+        Self {
+            game,
+            dark_clock: None,
+            light_clock: None,
+            forfeit: None,
+            forfeit_reason: None,
+            dark,
+            light,
+        }
+    }
+
+    // gives both players the same clock
+    pub fn set_clock(&mut self, clock: Clock) {
+        self.dark_clock = Some(clock);
+        self.light_clock = Some(clock);
     }
 
     pub fn play(&mut self) {
         while !self.game.is_complete() {
-            let player = match self.game.turn {
-                Disk::Dark => &mut self.dark,
-                Disk::Light => &mut self.light,
+            let turn = self.game.turn;
+            let (player, clock) = match turn {
+                Disk::Dark => (&mut self.dark, &mut self.dark_clock),
+                Disk::Light => (&mut self.light, &mut self.light_clock),
             };
 
             let playable_game = self.game.clone();
-            let result = player.play(playable_game);
+            let timer = Instant::now();
+            let result = player.play(playable_game, clock.as_ref());
+
+            if let Some(c) = clock {
+                if !c.charge(timer.elapsed()) {
+                    self.forfeit = Some(turn);
+                    self.forfeit_reason = Some(format!("{} ran out of time", turn));
+                    return;
+                }
+            }
+
+            // exactly one more move, which has to be the game the rules make of it
+            let game = &self.game;
+            let legal = result.transcript.last().and_then(|t| game.apply(*t).ok()).as_ref() == Some(&result);
+            if !legal {
+                self.forfeit = Some(turn);
+                self.forfeit_reason = Some(player.error().unwrap_or(format!("{} didn't make a legal move", turn)));
+                return;
+            }
+
             self.game = result;
         }
     }
+
+    // How the game came out, if it was played to the end.
+    pub fn result(&self) -> Option<GameResult> {
+        self.game.result()
+    }
+
+    // 1 if `disk` won, 0 for a draw and -1 if it lost, counting forfeits as losses;
+    // None if the contest hasn't finished.
+    pub fn outcome(&self, disk: Disk) -> Option<i32> {
+        match self.forfeit {
+            Some(loser) => Some(if loser == disk { -1 } else { 1 }),
+            None => self.result().map(|r| r.outcome(disk)),
+        }
+    }
+}
+
+pub trait Player {
+    // Plays one move (or a pass) for the side to move. `clock` is the player's
+    // remaining time, if the contest is timed.
+    fn play(&mut self, game: Game, clock: Option<&Clock>) -> Game;
+
+    // Why the player couldn't make a move, if it couldn't.
+    fn error(&self) -> Option<String> {
+        None
+    }
 }
 
+// Always plays the first available move.
 #[derive(Clone)]
-pub struct Player {
-    pub disk: Disk,
+pub struct FirstMove;
+
+impl Player for FirstMove {
+    fn play(&mut self, game: Game, _clock: Option<&Clock>) -> Game {
+        match game.legal_actions().first() {
+            Some(&t) => game.apply(t).expect("a legal action"),
+            None => game,
+        }
+    }
 }
 
-impl Player {
-    pub fn new() -> Self {
-        Self { disk: Disk::Dark }
+// Searches with iterative deepening, using its clock to budget time if it has one.
+pub struct Engine<E: Evaluator> {
+    pub deepening: Deepening<E>,
+    // used when the contest isn't timed
+    pub move_time: Duration,
+}
+
+impl<E: Evaluator> Engine<E> {
+    pub fn new(evaluator: E) -> Self {
+        let mut deepening = Deepening::new(evaluator);
+        deepening.set_table(Arc::new(TranspositionTable::new(16)));
+
+        Self {
+            deepening,
+            move_time: Duration::from_millis(100),
+        }
     }
+}
+
+impl<E: Evaluator> Player for Engine<E> {
+    fn play(&mut self, game: Game, clock: Option<&Clock>) -> Game {
+        let budget = match clock {
+            Some(c) => c.budget(&game),
+            None => self.move_time,
+        };
 
-    pub fn play(&mut self, game: Game) -> Game {
-        match game.move_iter().next() {
-            Some(m) => game.play(m),
+        let best = self.deepening.search(&game, Some(budget), |_| ()).result.best;
+
+        match best {
+            Some(t) => game.apply(t).expect("search returned an illegal move"),
             None => game.pass(),
         }
     }
 }
+
+// Plays from an opening book for the first `plies` moves of the game, and hands
+// over to another player once the book runs out.
+pub struct Booked {
+    pub book: Arc<Book>,
+    pub plies: usize,
+    player: Box<dyn Player>,
+    rng: StdRng,
+}
+
+impl Booked {
+    pub fn new(book: Arc<Book>, plies: usize, player: Box<dyn Player>, seed: Seed) -> Self {
+        Self {
+            book,
+            plies,
+            player,
+            rng: seed.rng(),
+        }
+    }
+}
+
+impl Player for Booked {
+    fn play(&mut self, game: Game, clock: Option<&Clock>) -> Game {
+        if game.transcript.len() < self.plies {
+            if let Some(t) = self.book.choose(&game, &mut self.rng) {
+                return game.apply(t).expect("book returned an illegal move");
+            }
+        }
+        self.player.play(game, clock)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::eval::heuristic::{Heuristic, Parity};
+    use crate::game::ValidMove;
+    use crate::transcript::Transcript;
+
+    // never answers in time
+    struct Sleeper;
+
+    impl Player for Sleeper {
+        fn play(&mut self, game: Game, _clock: Option<&Clock>) -> Game {
+            std::thread::sleep(Duration::from_millis(20));
+            FirstMove.play(game, None)
+        }
+    }
+
+    // records a legal move but doesn't flip anything
+    struct Cheat;
+
+    impl Player for Cheat {
+        fn play(&mut self, game: Game, _clock: Option<&Clock>) -> Game {
+            let position = game.move_iter().next().unwrap().position;
+            game.play(ValidMove { position, affected: Vec::new() })
+        }
+    }
+
+    #[test]
+    fn plays_to_completion() {
+        let mut contest = Contest::new(Game::new(), Box::new(FirstMove), Box::new(FirstMove));
+        contest.play();
+        assert!(contest.game.is_complete());
+        assert_eq!(contest.forfeit, None);
+        let result = contest.result().unwrap();
+        assert_eq!(contest.outcome(Disk::Dark), Some(result.outcome(Disk::Dark)));
+        assert_eq!(result.score.0 + result.score.1, 64);
+    }
+
+    #[test]
+    fn engines_on_the_clock() {
+        let mut dark = Engine::new(Heuristic::new());
+        dark.deepening.max_depth = 2;
+        let mut light = Engine::new(Parity);
+        light.deepening.max_depth = 2;
+
+        let mut contest = Contest::new(Game::new(), Box::new(dark), Box::new(light));
+        contest.set_clock(Clock::new(Duration::from_secs(30), Duration::from_millis(100)));
+        contest.play();
+
+        assert!(contest.game.is_complete());
+        assert_eq!(contest.forfeit, None);
+        assert!(contest.dark_clock.unwrap().remaining > Duration::from_secs(25));
+    }
+
+    #[test]
+    fn plays_from_the_book() {
+        // a book of one game, which is always the same opening under symmetry
+        let line = Game::from_transcript(Transcript::from_string("F5D6C3D3C4F4F6F3E6E7"));
+        let mut book = Book::new();
+        book.add_game(&line, 10);
+        let book = Arc::new(book);
+
+        let dark = Booked::new(book.clone(), 6, Box::new(FirstMove), Seed::from_string("dark".to_string()));
+        let light = Booked::new(book.clone(), 6, Box::new(FirstMove), Seed::from_string("light".to_string()));
+        let mut contest = Contest::new(Game::new(), Box::new(dark), Box::new(light));
+        contest.play();
+
+        // the first six moves come from the book
+        let opening = Game::from_transcript(contest.game.transcript[..6].to_vec());
+        assert!(book.get(&opening).is_some());
+        assert!(contest.game.is_complete());
+    }
+
+    #[test]
+    fn forfeits_on_timeout() {
+        let mut contest = Contest::new(Game::new(), Box::new(FirstMove), Box::new(Sleeper));
+        contest.set_clock(Clock::new(Duration::from_millis(50), Duration::from_secs(0)));
+        contest.play();
+
+        assert_eq!(contest.forfeit, Some(Disk::Light));
+        assert_eq!(contest.forfeit_reason.as_deref(), Some("Light ran out of time"));
+        assert!(!contest.game.is_complete());
+        assert_eq!(contest.result(), None);
+        assert_eq!(contest.outcome(Disk::Dark), Some(1));
+    }
+
+    #[test]
+    fn forfeits_on_an_illegal_move() {
+        let mut contest = Contest::new(Game::new(), Box::new(FirstMove), Box::new(Cheat));
+        contest.play();
+
+        assert_eq!(contest.forfeit, Some(Disk::Light));
+        assert_eq!(contest.forfeit_reason.as_deref(), Some("Light didn't make a legal move"));
+        assert_eq!(contest.game.transcript.len(), 1);
+    }
+}
@@ -21,4 +21,12 @@ impl Direction {
         Direction::West,
         Direction::NorthWest,
     ];
+
+    // the four lines through a square, as pairs of opposite directions
+    pub const AXES: [(Self, Self); 4] = [
+        (Direction::East, Direction::West),
+        (Direction::North, Direction::South),
+        (Direction::NorthEast, Direction::SouthWest),
+        (Direction::NorthWest, Direction::SouthEast),
+    ];
 }
@@ -1,6 +1,7 @@
 use std::fmt;
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Disk {
     Dark,
     Light,
@@ -34,6 +35,12 @@ impl DiskIter {
     }
 }
 
+impl Default for DiskIter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Iterator for DiskIter {
     type Item = Disk;
     fn next(&mut self) -> Option<Self::Item> {
@@ -0,0 +1,294 @@
+// Classic hand-tuned Othello heuristics, weighted by game phase.
+
+use crate::board::{Board, SIZE};
+use crate::direction::Direction;
+use crate::disk::Disk;
+use crate::eval::{Evaluator, DISC};
+use crate::game::{Game, ValidMoveIterator};
+use crate::position::PositionState;
+
+type Square = (usize, usize);
+
+// corners, with their diagonal X-square and the two orthogonal C-squares
+fn corner_squares(board: &Board) -> [(Square, Square, [Square; 2]); 4] {
+    let max = board.size() - 1;
+    [
+        ((0, 0), (1, 1), [(1, 0), (0, 1)]),
+        ((max, 0), (max - 1, 1), [(max - 1, 0), (max, 1)]),
+        ((0, max), (1, max - 1), [(1, max), (0, max - 1)]),
+        ((max, max), (max - 1, max - 1), [(max - 1, max), (max, max - 1)]),
+    ]
+}
+
+// Each feature is the difference between the player's count and the opponent's.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Features {
+    pub mobility: i32,
+    pub potential_mobility: i32,
+    pub corners: i32,
+    pub x_squares: i32,
+    pub c_squares: i32,
+    pub parity: i32,
+    pub stability: i32,
+}
+
+impl Features {
+    pub fn new(board: &Board, disk: Disk) -> Self {
+        let other = disk.opposite();
+        let diff = |f: &dyn Fn(Disk) -> usize| f(disk) as i32 - f(other) as i32;
+
+        Features {
+            mobility: diff(&|d| mobility(board, d)),
+            // an opponent disc next to an empty square is a potential move for us
+            potential_mobility: diff(&|d| frontier(board, d.opposite())),
+            corners: diff(&|d| corners(board, d)),
+            x_squares: diff(&|d| x_squares(board, d)),
+            c_squares: diff(&|d| c_squares(board, d)),
+            parity: diff(&|d| discs(board, d)),
+            stability: diff(&|d| board.stable_discs(d).len()),
+        }
+    }
+
+    // The same features as they count in anti-reversi, where discs that stay are a
+    // liability: corners, stable discs and disc count change sides, and the squares
+    // that give corners away become the ones to hold. Mobility matters either way.
+    pub fn anti(&self) -> Self {
+        Features {
+            mobility: self.mobility,
+            potential_mobility: self.potential_mobility,
+            corners: -self.corners,
+            x_squares: -self.x_squares,
+            c_squares: -self.c_squares,
+            parity: -self.parity,
+            stability: -self.stability,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Weights {
+    pub mobility: i32,
+    pub potential_mobility: i32,
+    pub corners: i32,
+    pub x_squares: i32,
+    pub c_squares: i32,
+    pub parity: i32,
+    pub stability: i32,
+}
+
+impl Weights {
+    pub fn apply(&self, f: &Features) -> i32 {
+        self.mobility * f.mobility
+            + self.potential_mobility * f.potential_mobility
+            + self.corners * f.corners
+            + self.x_squares * f.x_squares
+            + self.c_squares * f.c_squares
+            + self.parity * f.parity
+            + self.stability * f.stability
+    }
+}
+
+// Phase boundaries, by number of discs on the standard board; boards with other
+// numbers of playable squares are scaled to match.
+const OPENING_DISCS: usize = 20;
+const MIDGAME_DISCS: usize = 44;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Heuristic {
+    pub opening: Weights,
+    pub midgame: Weights,
+    pub endgame: Weights,
+}
+
+impl Heuristic {
+    pub fn new() -> Self {
+        Heuristic {
+            opening: Weights {
+                mobility: 80,
+                potential_mobility: 40,
+                corners: 800,
+                x_squares: -300,
+                c_squares: -100,
+                parity: -10,
+                stability: 100,
+            },
+            midgame: Weights {
+                mobility: 60,
+                potential_mobility: 30,
+                corners: 800,
+                x_squares: -250,
+                c_squares: -80,
+                parity: 0,
+                stability: 150,
+            },
+            endgame: Weights {
+                mobility: 30,
+                potential_mobility: 10,
+                corners: 600,
+                x_squares: -100,
+                c_squares: -40,
+                parity: DISC,
+                stability: 200,
+            },
+        }
+    }
+
+    pub fn weights(&self, board: &Board) -> &Weights {
+        let count = discs(board, Disk::Dark) + discs(board, Disk::Light);
+        let count = count * SIZE * SIZE / (count + board.empties());
+        if count <= OPENING_DISCS {
+            &self.opening
+        } else if count <= MIDGAME_DISCS {
+            &self.midgame
+        } else {
+            &self.endgame
+        }
+    }
+}
+
+impl Default for Heuristic {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Evaluator for Heuristic {
+    fn evaluate(&self, game: &Game, disk: Disk) -> i32 {
+        let board = game.board();
+        let mut features = Features::new(board, disk);
+        if game.rules().anti {
+            features = features.anti();
+        }
+        self.weights(board).apply(&features)
+    }
+}
+
+// Disc count alone, as the rules count it; the greedy baseline.
+#[derive(Clone, Copy, Debug)]
+pub struct Parity;
+
+impl Evaluator for Parity {
+    fn evaluate(&self, game: &Game, disk: Disk) -> i32 {
+        let board = game.board();
+        game.rules().outcome(discs(board, disk) as i32 - discs(board, disk.opposite()) as i32) * DISC
+    }
+}
+
+pub fn mobility(board: &Board, disk: Disk) -> usize {
+    ValidMoveIterator::new(*board, disk).count()
+}
+
+// discs next to at least one empty square
+pub fn frontier(board: &Board, disk: Disk) -> usize {
+    let state: PositionState = disk.into();
+    board
+        .iter()
+        .filter(|p| board.get(p.x, p.y) == state)
+        .filter(|p| {
+            Direction::ALL.iter().any(|d| match p.neighbor(d, board.size()) {
+                Some(n) => board.get(n.x, n.y) == PositionState::Empty,
+                None => false,
+            })
+        })
+        .count()
+}
+
+pub fn corners(board: &Board, disk: Disk) -> usize {
+    let state: PositionState = disk.into();
+    corner_squares(board).iter().filter(|((x, y), _, _)| board.get(*x, *y) == state).count()
+}
+
+// X-squares held while their corner is still open
+pub fn x_squares(board: &Board, disk: Disk) -> usize {
+    let state: PositionState = disk.into();
+    corner_squares(board)
+        .iter()
+        .filter(|((cx, cy), _, _)| board.get(*cx, *cy) == PositionState::Empty)
+        .filter(|(_, (x, y), _)| board.get(*x, *y) == state)
+        .count()
+}
+
+// C-squares held while their corner is still open
+pub fn c_squares(board: &Board, disk: Disk) -> usize {
+    let state: PositionState = disk.into();
+    corner_squares(board)
+        .iter()
+        .filter(|((cx, cy), _, _)| board.get(*cx, *cy) == PositionState::Empty)
+        .map(|(_, _, cs)| cs.iter().filter(|(x, y)| board.get(*x, *y) == state).count())
+        .sum()
+}
+
+pub fn discs(board: &Board, disk: Disk) -> usize {
+    let state: PositionState = disk.into();
+    board.iter().filter(|p| board.get(p.x, p.y) == state).count()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rules::Rules;
+    use crate::transcript::{Transcript, MANUBU_MARUO};
+
+    #[test]
+    fn opening_features() {
+        let g = Game::new();
+        let f = Features::new(g.board(), Disk::Dark);
+
+        // the starting position is perfectly symmetrical
+        assert_eq!(f, Features::default());
+        assert_eq!(mobility(g.board(), Disk::Dark), 4);
+        assert_eq!(frontier(g.board(), Disk::Light), 2);
+    }
+
+    #[test]
+    fn square_features() {
+        let mut b = Board::empty();
+        b.set(0, 0, PositionState::Dark);
+        b.set(1, 0, PositionState::Dark);
+        b.set(2, 0, PositionState::Dark);
+        b.set(4, 0, PositionState::Dark);
+        b.set(6, 6, PositionState::Light);
+        b.set(7, 6, PositionState::Light);
+        b.set(1, 1, PositionState::Light);
+
+        assert_eq!(corners(&b, Disk::Dark), 1);
+
+        // the X-square next to an owned corner is no longer a liability
+        assert_eq!(x_squares(&b, Disk::Light), 1);
+        assert_eq!(c_squares(&b, Disk::Light), 1);
+        assert_eq!(c_squares(&b, Disk::Dark), 0);
+
+        // on a smaller board the same squares are the corner region
+        let mut small = Board::with_size(6);
+        small.set(5, 5, PositionState::Dark);
+        small.set(4, 4, PositionState::Light);
+        assert_eq!(corners(&small, Disk::Dark), 1);
+        assert_eq!(x_squares(&small, Disk::Light), 0);
+        small.set(5, 5, PositionState::Empty);
+        assert_eq!(x_squares(&small, Disk::Light), 1);
+    }
+
+    #[test]
+    fn evaluators() {
+        let g = Game::from_transcript(Transcript::from_string(MANUBU_MARUO));
+        assert_eq!(Parity.evaluate(&g, Disk::Dark), 13 * DISC);
+        assert_eq!(Parity.evaluate(&g, Disk::Light), -13 * DISC);
+
+        let h = Heuristic::new();
+        assert_eq!(h.evaluate(&Game::new(), Disk::Dark), 0);
+        assert_eq!(h.evaluate(&g, Disk::Dark), -h.evaluate(&g, Disk::Light));
+
+        // wiping out the opponent is the worst thing that can happen in anti-reversi
+        let anti = Game::with_rules(8, Rules::anti()).replay(g.transcript.clone());
+        assert_eq!(Parity.evaluate(&anti, Disk::Dark), -13 * DISC);
+
+        // features about holding discs change sides there, while mobility still helps
+        let g = Game::from_transcript(Transcript::from_string("F5D6C3D3C4F4F6F3E6E7D7"));
+        let f = Features::new(g.board(), Disk::Dark);
+        let anti = f.anti();
+        assert_eq!(anti.mobility, f.mobility);
+        assert_eq!(anti.parity, -f.parity);
+        let position = Game::with_rules(8, Rules::anti()).replay(g.transcript.clone());
+        assert_eq!(h.evaluate(&position, Disk::Dark), h.weights(g.board()).apply(&anti));
+    }
+}
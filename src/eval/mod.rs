@@ -0,0 +1,26 @@
+pub mod heuristic;
+pub mod pattern;
+pub mod training;
+
+use crate::disk::Disk;
+use crate::game::Game;
+
+// Evaluations are in hundredths of a disc, so a completed game evaluates to
+// exactly its final disc differential times DISC.
+pub const DISC: i32 = 100;
+
+pub trait Evaluator {
+    // Static score of the game from the point of view of `disk`; positive is good for `disk`.
+    fn evaluate(&self, game: &Game, disk: Disk) -> i32;
+}
+
+// The exact outcome of a completed game, from the point of view of `disk`, under
+// the game's rules.
+pub fn final_score(game: &Game, disk: Disk) -> i32 {
+    let (dark, light) = game.score();
+    let differential = game.rules().outcome(dark as i32 - light as i32);
+    match disk {
+        Disk::Dark => differential * DISC,
+        Disk::Light => -differential * DISC,
+    }
+}
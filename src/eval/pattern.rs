@@ -0,0 +1,402 @@
+// Logistello-style pattern evaluation: each pattern is a fixed group of squares
+// whose contents, read as a base-3 number, index a table of learned weights.
+// Every pattern is applied under all eight board symmetries with a shared table,
+// and there is a separate set of tables for each phase of the game. The patterns
+// are laid out for the standard board, so weights don't carry over to other sizes.
+
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::Path;
+
+use crate::board::{Board, SIZE};
+use crate::disk::Disk;
+use crate::eval::{Evaluator, DISC};
+use crate::game::Game;
+use crate::position::{Position, PositionState};
+use crate::rules::{Rules, Supply};
+
+const MAGIC: &[u8; 4] = b"RVPW";
+const VERSION: u32 = 1;
+
+// phases are buckets of four discs on the board
+pub const PHASES: usize = 15;
+
+// base instances of each pattern, as (x, y) squares
+const SHAPES: [&[(usize, usize)]; 11] = [
+    // edge plus both X-squares
+    &[(0, 0), (1, 0), (2, 0), (3, 0), (4, 0), (5, 0), (6, 0), (7, 0), (1, 1), (6, 1)],
+    // 3x3 corner
+    &[(0, 0), (1, 0), (2, 0), (0, 1), (1, 1), (2, 1), (0, 2), (1, 2), (2, 2)],
+    // 2x5 corner
+    &[(0, 0), (1, 0), (2, 0), (3, 0), (4, 0), (0, 1), (1, 1), (2, 1), (3, 1), (4, 1)],
+    // second, third and fourth rows
+    &[(0, 1), (1, 1), (2, 1), (3, 1), (4, 1), (5, 1), (6, 1), (7, 1)],
+    &[(0, 2), (1, 2), (2, 2), (3, 2), (4, 2), (5, 2), (6, 2), (7, 2)],
+    &[(0, 3), (1, 3), (2, 3), (3, 3), (4, 3), (5, 3), (6, 3), (7, 3)],
+    // diagonals of length eight down to four
+    &[(0, 0), (1, 1), (2, 2), (3, 3), (4, 4), (5, 5), (6, 6), (7, 7)],
+    &[(0, 1), (1, 2), (2, 3), (3, 4), (4, 5), (5, 6), (6, 7)],
+    &[(0, 2), (1, 3), (2, 4), (3, 5), (4, 6), (5, 7)],
+    &[(0, 3), (1, 4), (2, 5), (3, 6), (4, 7)],
+    &[(0, 4), (1, 5), (2, 6), (3, 7)],
+];
+
+
+#[derive(Clone, Debug)]
+pub struct Shape {
+    // every distinct symmetrical image of the base squares, in a consistent order
+    pub instances: Vec<Vec<Position>>,
+}
+
+impl Shape {
+    pub fn size(&self) -> usize {
+        self.instances[0].len()
+    }
+
+    pub fn table_size(&self) -> usize {
+        3usize.pow(self.size() as u32)
+    }
+
+    pub fn all() -> Vec<Shape> {
+        SHAPES
+            .iter()
+            .map(|squares| {
+                let base: Vec<Position> = squares.iter().map(|(x, y)| Position { x: *x, y: *y }).collect();
+                let mut instances: Vec<Vec<Position>> = Vec::new();
+                for symmetry in &Position::SYMMETRIES {
+                    let image: Vec<Position> = base.iter().map(|p| symmetry(*p, SIZE)).collect();
+                    if !instances.contains(&image) {
+                        instances.push(image);
+                    }
+                }
+                Shape { instances }
+            })
+            .collect()
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Pattern {
+    pub shapes: Vec<Shape>,
+    // per phase, the constant term
+    pub bias: Vec<f32>,
+    // per phase, per shape, one weight (in discs) for every configuration
+    pub weights: Vec<Vec<Vec<f32>>>,
+    // the rules of the games the weights were learned from
+    pub rules: Rules,
+}
+
+impl Pattern {
+    // all weights zero; load or train before use
+    pub fn new() -> Self {
+        let shapes = Shape::all();
+        let tables: Vec<Vec<f32>> = shapes.iter().map(|s| vec![0.0; s.table_size()]).collect();
+
+        Pattern {
+            bias: vec![0.0; PHASES],
+            weights: vec![tables; PHASES],
+            shapes,
+            rules: Rules::new(),
+        }
+    }
+
+    // The patterns are laid out for the standard board, and can't read any other.
+    pub fn check(board: &Board) -> Result<(), String> {
+        if board.size() == SIZE {
+            Ok(())
+        } else {
+            let size = board.size();
+            Err(format!("pattern weights are for the {}x{} board, not {}x{}", SIZE, SIZE, size, size))
+        }
+    }
+
+    pub fn phase(board: &Board) -> usize {
+        let discs = board
+            .iter()
+            .filter(|p| matches!(board.get(p.x, p.y), PositionState::Dark | PositionState::Light))
+            .count();
+        (discs.saturating_sub(4) / 4).min(PHASES - 1)
+    }
+
+    // Every (shape, configuration index) pair present on the board, from the point
+    // of view of `disk`: 0 is empty, 1 is `disk` and 2 is the opponent. Instances
+    // with a blocked square in them are left out, as no weights are learned for
+    // squares that can never hold a disc.
+    pub fn indices(&self, board: &Board, disk: Disk) -> Vec<(usize, usize)> {
+        if let Err(e) = Pattern::check(board) {
+            panic!("{}", e);
+        }
+        let own: PositionState = disk.into();
+        let mut indices = Vec::new();
+
+        for (s, shape) in self.shapes.iter().enumerate() {
+            for instance in &shape.instances {
+                if instance.iter().any(|p| board.get(p.x, p.y) == PositionState::Blocked) {
+                    continue;
+                }
+                let index = instance.iter().fold(0, |index, p| {
+                    let digit = match board.get(p.x, p.y) {
+                        PositionState::Empty => 0,
+                        state if state == own => 1,
+                        _ => 2,
+                    };
+                    index * 3 + digit
+                });
+                indices.push((s, index));
+            }
+        }
+
+        indices
+    }
+
+    // predicted final disc differential for `disk`
+    pub fn predict(&self, board: &Board, disk: Disk) -> f32 {
+        let phase = Pattern::phase(board);
+        let tables = &self.weights[phase];
+        self.indices(board, disk)
+            .into_iter()
+            .fold(self.bias[phase], |sum, (s, index)| sum + tables[s][index])
+    }
+
+    // Weight files are little-endian: the magic bytes, the format version, the
+    // rules (anti and placement flags, then the kind of supply and its size), the
+    // number of phases and of shapes, each shape's square count, and then for
+    // every phase its bias followed by every shape's table as f32s.
+    pub fn load<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let mut reader = BufReader::new(File::open(path)?);
+        let invalid = |message: &str| io::Error::new(io::ErrorKind::InvalidData, message.to_string());
+
+        let mut magic = [0u8; 4];
+        reader.read_exact(&mut magic)?;
+        if &magic != MAGIC {
+            return Err(invalid("not a pattern weight file"));
+        }
+        let version = read_u32(&mut reader)?;
+        if version != VERSION {
+            return Err(invalid("unsupported pattern weight file version"));
+        }
+
+        let mut pattern = Pattern::new();
+        let anti = read_u32(&mut reader)? != 0;
+        let placement = read_u32(&mut reader)? != 0;
+        let supply = match (read_u32(&mut reader)?, read_u32(&mut reader)? as usize) {
+            (0, _) => Supply::Unlimited,
+            (1, n) => Supply::Pass(n),
+            (2, n) => Supply::HandOver(n),
+            _ => return Err(invalid("unknown rules in pattern weight file")),
+        };
+        pattern.rules = Rules { anti, placement, supply };
+        if read_u32(&mut reader)? as usize != PHASES || read_u32(&mut reader)? as usize != pattern.shapes.len() {
+            return Err(invalid("pattern weight file doesn't match this evaluator"));
+        }
+        for shape in &pattern.shapes {
+            if read_u32(&mut reader)? as usize != shape.size() {
+                return Err(invalid("pattern weight file doesn't match this evaluator"));
+            }
+        }
+
+        for phase in 0..PHASES {
+            pattern.bias[phase] = read_f32(&mut reader)?;
+            for table in pattern.weights[phase].iter_mut() {
+                for weight in table.iter_mut() {
+                    *weight = read_f32(&mut reader)?;
+                }
+            }
+        }
+
+        Ok(pattern)
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        let mut writer = BufWriter::new(File::create(path)?);
+
+        writer.write_all(MAGIC)?;
+        writer.write_all(&VERSION.to_le_bytes())?;
+        let (kind, discs) = match self.rules.supply {
+            Supply::Unlimited => (0u32, 0),
+            Supply::Pass(n) => (1, n),
+            Supply::HandOver(n) => (2, n),
+        };
+        for field in &[self.rules.anti as u32, self.rules.placement as u32, kind, discs as u32] {
+            writer.write_all(&field.to_le_bytes())?;
+        }
+        writer.write_all(&(PHASES as u32).to_le_bytes())?;
+        writer.write_all(&(self.shapes.len() as u32).to_le_bytes())?;
+        for shape in &self.shapes {
+            writer.write_all(&(shape.size() as u32).to_le_bytes())?;
+        }
+
+        for phase in 0..PHASES {
+            writer.write_all(&self.bias[phase].to_le_bytes())?;
+            for table in &self.weights[phase] {
+                for weight in table {
+                    writer.write_all(&weight.to_le_bytes())?;
+                }
+            }
+        }
+
+        writer.flush()
+    }
+}
+
+impl Default for Pattern {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Evaluator for Pattern {
+    // The weights predict results as the rules they were learned under count them,
+    // so weights learned for one of reversi and anti-reversi are turned around for
+    // the other.
+    fn evaluate(&self, game: &Game, disk: Disk) -> i32 {
+        let prediction = self.predict(game.board(), disk);
+        let prediction = if game.rules().anti == self.rules.anti { prediction } else { -prediction };
+        (prediction * DISC as f32).round() as i32
+    }
+}
+
+fn read_u32<R: Read>(reader: &mut R) -> io::Result<u32> {
+    let mut bytes = [0u8; 4];
+    reader.read_exact(&mut bytes)?;
+    Ok(u32::from_le_bytes(bytes))
+}
+
+fn read_f32<R: Read>(reader: &mut R) -> io::Result<f32> {
+    let mut bytes = [0u8; 4];
+    reader.read_exact(&mut bytes)?;
+    Ok(f32::from_le_bytes(bytes))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::layout;
+    use crate::transcript::Transcript;
+
+    // indices into SHAPES
+    const ROW4: usize = 5;
+    const DIAGONAL8: usize = 6;
+    const DIAGONAL7: usize = 7;
+
+    // deterministic, arbitrary weights
+    fn scrambled() -> Pattern {
+        let mut pattern = Pattern::new();
+        let mut seed: u32 = 1;
+        for phase in pattern.weights.iter_mut() {
+            for table in phase.iter_mut() {
+                for weight in table.iter_mut() {
+                    seed = seed.wrapping_mul(1_103_515_245).wrapping_add(12_345);
+                    *weight = (seed >> 16) as f32 / 65536.0 - 0.5;
+                }
+            }
+        }
+        pattern
+    }
+
+    #[test]
+    fn shapes() {
+        let shapes = Shape::all();
+        assert_eq!(shapes.len(), 11);
+
+        // edges and 2x5 corners have eight distinct images; the main diagonal maps onto
+        // itself, leaving both diagonals in both directions.
+        assert_eq!(shapes[0].instances.len(), 8);
+        assert_eq!(shapes[2].instances.len(), 8);
+        assert_eq!(shapes[6].instances.len(), 4);
+        assert_eq!(shapes[0].table_size(), 59049);
+    }
+
+    #[test]
+    fn indices() {
+        let p = Pattern::new();
+        let empty = Board::empty();
+        assert!(p.indices(&empty, Disk::Dark).iter().all(|(_, index)| *index == 0));
+
+        // the starting position only touches the middle rows and the main diagonals
+        let g = Game::new();
+        let indices = p.indices(g.board(), Disk::Dark);
+        for (s, index) in indices {
+            let touched = matches!(s, ROW4 | DIAGONAL8 | DIAGONAL7);
+            assert_eq!(index != 0, touched);
+        }
+        assert_eq!(Pattern::phase(g.board()), 0);
+
+        // D4 is light and E4 dark: reading row 4 left to right gives 0 0 0 2 1 0 0 0
+        let row4 = p.indices(g.board(), Disk::Dark).into_iter().find(|(s, _)| *s == ROW4);
+        assert_eq!(row4, Some((ROW4, 2 * 81 + 27)));
+    }
+
+    #[test]
+    fn blocked_squares() {
+        // a blocked corner isn't a disc, and takes out every pattern it's part of
+        let p = Pattern::new();
+        let mut board = layout::standard(8);
+        let all = p.indices(&board, Disk::Dark).len();
+        board.set(0, 0, PositionState::Blocked);
+        assert_eq!(Pattern::phase(&board), 0);
+        // two edges and the main diagonal read both ways, and the corner's 3x3 and
+        // 2x5 squares read across and down
+        assert_eq!(p.indices(&board, Disk::Dark).len(), all - 10);
+    }
+
+    #[test]
+    fn standard_board_only() {
+        assert!(Pattern::check(Game::new().board()).is_ok());
+        assert_eq!(
+            Pattern::check(Game::with_size(6).board()),
+            Err(String::from("pattern weights are for the 8x8 board, not 6x6"))
+        );
+    }
+
+    #[test]
+    fn symmetrical() {
+        let pattern = scrambled();
+        let transcript = Transcript::from_string("F5D6C3D3C4F4F6F3E6E7");
+        let expected = pattern.evaluate(&Game::from_transcript(transcript.clone()), Disk::Dark);
+
+        for t in Transcript::symmetrical(transcript, SIZE) {
+            assert_eq!(pattern.evaluate(&Game::from_transcript(t), Disk::Dark), expected);
+        }
+    }
+
+    #[test]
+    fn save_and_load() {
+        let mut pattern = scrambled();
+        pattern.rules = Rules { supply: Supply::HandOver(30), ..Rules::anti() };
+        let path = std::env::temp_dir().join(format!("reversi-pattern-saved-{}.bin", std::process::id()));
+        pattern.save(&path).unwrap();
+        let loaded = Pattern::load(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(loaded.bias, pattern.bias);
+        assert_eq!(loaded.weights, pattern.weights);
+        assert_eq!(loaded.rules, pattern.rules);
+    }
+
+    #[test]
+    fn anti_reversi() {
+        // weights for one game are turned around for the other
+        let mut pattern = scrambled();
+        let transcript = Transcript::from_string("F5D6C3D3C4F4F6F3E6E7");
+        let standard = Game::from_transcript(transcript.clone());
+        let anti = Game::with_rules(8, Rules::anti()).replay(transcript);
+        let score = pattern.evaluate(&standard, Disk::Dark);
+        assert_eq!(pattern.evaluate(&anti, Disk::Dark), -score);
+
+        pattern.rules = Rules::anti();
+        assert_eq!(pattern.evaluate(&anti, Disk::Dark), score);
+        assert_eq!(pattern.evaluate(&standard, Disk::Dark), -score);
+    }
+
+    #[test]
+    fn load_rejects_other_files() {
+        let path = std::env::temp_dir().join(format!("reversi-pattern-{}.bin", std::process::id()));
+        std::fs::write(&path, b"GIF89a").unwrap();
+        let result = Pattern::load(&path);
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(result.unwrap_err().kind(), io::ErrorKind::InvalidData);
+        assert!(Pattern::load("/nonexistent/weights.bin").is_err());
+    }
+}
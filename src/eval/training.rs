@@ -0,0 +1,194 @@
+// Fits pattern weights to labeled positions by stochastic gradient descent on
+// the squared error, with L2 regularization pulling unused weights toward zero.
+
+use rand::prelude::*;
+
+use crate::board::SIZE;
+use crate::disk::Disk;
+use crate::eval::pattern::Pattern;
+use crate::eval::{final_score, DISC};
+use crate::game::Game;
+use crate::import;
+use crate::rules::Rules;
+
+struct Sample {
+    phase: usize,
+    indices: Vec<(usize, usize)>,
+    // final disc differential for the side the indices were read for
+    target: f32,
+}
+
+pub struct Trainer {
+    pub pattern: Pattern,
+    pub rate: f32,
+    pub lambda: f32,
+    samples: Vec<Sample>,
+}
+
+impl Trainer {
+    // Trains for the rules the pattern's weights are for.
+    pub fn new(pattern: Pattern) -> Self {
+        Trainer {
+            pattern,
+            rate: 0.002,
+            lambda: 0.0001,
+            samples: Vec::new(),
+        }
+    }
+
+    // Trains the pattern's weights for games under `rules`.
+    pub fn with_rules(mut pattern: Pattern, rules: Rules) -> Self {
+        pattern.rules = rules;
+        Trainer::new(pattern)
+    }
+
+    pub fn len(&self) -> usize {
+        self.samples.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.samples.is_empty()
+    }
+
+    // Labels every position of a completed game with its final result, as its rules
+    // count it; which have to be the rules being trained for.
+    pub fn add_game(&mut self, game: &Game) {
+        assert_eq!(game.rules(), self.pattern.rules, "games must be played under the rules being trained for");
+        let differential = final_score(game, Disk::Dark) / DISC;
+
+        let mut position = game.restart();
+        self.add_position(&position, differential);
+        for t in &game.transcript {
+            position = position.apply(*t).expect("the game's own moves replay");
+            self.add_position(&position, differential);
+        }
+    }
+
+    // `differential` is dark's final result in discs. Each position is
+    // learned from both sides, so the weights stay color symmetrical.
+    pub fn add_position(&mut self, game: &Game, differential: i32) {
+        let board = game.board();
+        let phase = Pattern::phase(board);
+
+        for (disk, target) in &[(Disk::Dark, differential), (Disk::Light, -differential)] {
+            self.samples.push(Sample {
+                phase,
+                indices: self.pattern.indices(board, *disk),
+                target: *target as f32,
+            });
+        }
+    }
+
+    // Reads lines of `<transcript> [differential]`, for games under the rules being
+    // trained for. Without a differential the transcript must be a complete game,
+    // and every position in it is used.
+    pub fn add_line(&mut self, line: &str) -> Result<(), String> {
+        let mut fields = line.split_whitespace();
+        let transcript = match fields.next() {
+            Some(t) => t,
+            None => return Ok(()),
+        };
+        let start = Game::with_rules(SIZE, self.pattern.rules);
+        let game = start.try_replay(&import::parse(transcript, &start)?.transcript, false)?;
+
+        match fields.next() {
+            Some(label) => {
+                let differential = label.parse().map_err(|_| format!("differential {} must be a number", label))?;
+                self.add_position(&game, differential);
+            }
+            None if game.is_complete() => self.add_game(&game),
+            None => return Err(String::from("unlabeled transcripts must be complete games")),
+        }
+        Ok(())
+    }
+
+    // One pass over the shuffled samples; returns the root mean squared error seen during the pass.
+    pub fn epoch<R: Rng>(&mut self, rng: &mut R) -> f32 {
+        self.samples.shuffle(rng);
+
+        let mut squared_error = 0.0;
+        for sample in &self.samples {
+            let bias = &mut self.pattern.bias[sample.phase];
+            let tables = &mut self.pattern.weights[sample.phase];
+
+            let prediction = sample.indices.iter().fold(*bias, |sum, (s, i)| sum + tables[*s][*i]);
+            let error = prediction - sample.target;
+            squared_error += error * error;
+
+            *bias -= self.rate * error;
+            for (s, i) in &sample.indices {
+                let weight = &mut tables[*s][*i];
+                *weight -= self.rate * (error + self.lambda * *weight);
+            }
+        }
+
+        (squared_error / self.samples.len().max(1) as f32).sqrt()
+    }
+
+    pub fn rmse(&self) -> f32 {
+        let squared_error: f32 = self
+            .samples
+            .iter()
+            .map(|sample| {
+                let tables = &self.pattern.weights[sample.phase];
+                let prediction = sample
+                    .indices
+                    .iter()
+                    .fold(self.pattern.bias[sample.phase], |sum, (s, i)| sum + tables[*s][*i]);
+                (prediction - sample.target).powi(2)
+            })
+            .sum();
+
+        (squared_error / self.samples.len().max(1) as f32).sqrt()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::solvers::random::{Random, Seed};
+    use crate::transcript::MANUBU_MARUO;
+
+    #[test]
+    fn labels() {
+        let mut trainer = Trainer::new(Pattern::new());
+        trainer.add_line(MANUBU_MARUO).unwrap();
+        // ten positions, each seen from both sides
+        assert_eq!(trainer.len(), 20);
+
+        trainer.add_line("F5D6 -4").unwrap();
+        assert_eq!(trainer.len(), 22);
+        assert_eq!(trainer.samples[20].target, -4.0);
+        assert_eq!(trainer.samples[21].target, 4.0);
+
+        // lines that can't be read are reported, not learned from
+        assert!(trainer.add_line("F5D6").is_err());
+        assert!(trainer.add_line("F5D6 x").is_err());
+        assert!(trainer.add_line("F5Z9 -4").is_err());
+        assert_eq!(trainer.len(), 22);
+
+        // in anti-reversi, being wiped out is the best result there is
+        let mut trainer = Trainer::with_rules(Pattern::new(), Rules::anti());
+        trainer.add_line(MANUBU_MARUO).unwrap();
+        assert_eq!(trainer.pattern.rules, Rules::anti());
+        assert_eq!(trainer.samples[0].target, -13.0);
+    }
+
+    #[test]
+    fn error_decreases() {
+        let mut trainer = Trainer::new(Pattern::new());
+        let mut games = Random::new(Game::new(), Seed::from_string("training".to_string()));
+        for _ in 0..20 {
+            trainer.add_game(&games.next().unwrap());
+        }
+
+        let mut rng = StdRng::seed_from_u64(0);
+        let before = trainer.rmse();
+        for _ in 0..5 {
+            trainer.epoch(&mut rng);
+        }
+
+        // early positions recur with different outcomes, so some error is irreducible
+        assert!(trainer.rmse() < before * 0.75);
+    }
+}
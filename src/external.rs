@@ -0,0 +1,206 @@
+use std::io::{self, BufRead, BufReader, Write};
+use std::process::{Child, ChildStdin, Command, Stdio};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::board::SIZE;
+use crate::clock::Clock;
+use crate::contest::Player;
+use crate::game::Game;
+use crate::nboard::{parse_move, to_ggf};
+use crate::transcript::Transcript;
+
+// A program like Edax, run as a separate process and played through the NBoard
+// protocol: before each move it's sent the whole game with `set game`, then asked
+// for a move with `go`, and answers `=== <move>`.
+//
+// If the engine crashes, doesn't answer in time or answers with an illegal move it
+// makes no move at all, which forfeits the contest, and `error` says why.
+pub struct External {
+    pub name: String,
+    // how long to wait for a move when the contest isn't timed
+    pub timeout: Duration,
+    pub error: Option<String>,
+    child: Child,
+    stdin: ChildStdin,
+    lines: Receiver<String>,
+    pings: usize,
+}
+
+impl External {
+    // Starts the engine and says hello; `depth` is passed on with `set depth`.
+    pub fn spawn(program: &str, args: &[&str], depth: usize) -> io::Result<Self> {
+        let mut child = Command::new(program)
+            .args(args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()?;
+
+        let stdin = child.stdin.take().expect("child has no stdin");
+        let stdout = child.stdout.take().expect("child has no stdout");
+
+        // lines are read on their own thread, so that waiting for them can time out
+        let (sender, lines) = mpsc::channel();
+        thread::spawn(move || {
+            for line in BufReader::new(stdout).lines().map_while(Result::ok) {
+                if sender.send(line).is_err() {
+                    return;
+                }
+            }
+        });
+
+        let mut external = Self {
+            name: program.to_string(),
+            timeout: Duration::from_secs(10),
+            error: None,
+            child,
+            stdin,
+            lines,
+            pings: 0,
+        };
+        external.send("nboard 2")?;
+        external.send(&format!("set depth {}", depth))?;
+        Ok(external)
+    }
+
+    fn send(&mut self, command: &str) -> io::Result<()> {
+        writeln!(self.stdin, "{}", command)?;
+        self.stdin.flush()
+    }
+
+    // Reads lines until one starts with `prefix`, returning the rest of it. Engines
+    // send all sorts of other things along the way, like `status` and `set myname`.
+    fn expect(&mut self, prefix: &str, deadline: Instant) -> Result<String, String> {
+        loop {
+            let wait = deadline.saturating_duration_since(Instant::now());
+            match self.lines.recv_timeout(wait) {
+                Ok(line) => {
+                    if let Some(rest) = line.trim().strip_prefix(prefix) {
+                        return Ok(rest.trim().to_string());
+                    }
+                    if let Some(name) = line.trim().strip_prefix("set myname ") {
+                        self.name = name.to_string();
+                    }
+                }
+                Err(RecvTimeoutError::Timeout) => return Err(format!("{} didn't answer in time", self.name)),
+                Err(RecvTimeoutError::Disconnected) => return Err(format!("{} exited", self.name)),
+            }
+        }
+    }
+
+    fn request_move(&mut self, game: &Game, deadline: Instant) -> Result<Game, String> {
+        let crashed = |e: io::Error| format!("unable to talk to the engine: {}", e);
+
+        // the ping makes sure any earlier answers are out of the way
+        self.pings += 1;
+        self.send(&format!("set game {}", to_ggf(game)?)).map_err(crashed)?;
+        self.send(&format!("ping {}", self.pings)).map_err(crashed)?;
+        let ping = self.pings.to_string();
+        while self.expect("pong", deadline)? != ping {}
+
+        self.send("go").map_err(crashed)?;
+        let answer = self.expect("===", deadline)?;
+        let mv = answer.split('/').next().unwrap_or("");
+
+        // engines are only asked when there's a move to make
+        match parse_move(mv).filter(|t| *t != Transcript::Pass).map(|t| game.apply(t)) {
+            Some(Ok(next)) => Ok(next),
+            _ => Err(format!("{} played the illegal move {}", self.name, mv)),
+        }
+    }
+}
+
+impl Player for External {
+    fn play(&mut self, game: Game, clock: Option<&Clock>) -> Game {
+        // engines aren't asked when there's nothing to do but pass
+        if game.valid_moves().is_empty() {
+            return game.pass();
+        }
+        if self.error.is_some() {
+            return game;
+        }
+        if game.board().size() != SIZE {
+            self.error = Some(format!("{} only plays on the standard board", self.name));
+            return game;
+        }
+        if game.rules().placement || game.rules().supply.discs().is_some() {
+            self.error = Some(format!("{} only plays with the centre discs set up and unlimited discs", self.name));
+            return game;
+        }
+
+        let deadline = Instant::now() + clock.map_or(self.timeout, |c| c.remaining);
+        match self.request_move(&game, deadline) {
+            Ok(next) => next,
+            Err(e) => {
+                self.error = Some(e);
+                game
+            }
+        }
+    }
+
+    fn error(&self) -> Option<String> {
+        self.error.clone()
+    }
+}
+
+impl Drop for External {
+    fn drop(&mut self) {
+        let _ = self.send("quit");
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::contest::{Contest, FirstMove};
+    use crate::disk::Disk;
+
+    // a pretend engine, as a shell script that answers every ping and plays `answer`
+    fn scripted(answer: &str) -> External {
+        let script = format!(
+            "while read line; do case \"$line\" in ping*) echo \"pong ${{line#ping }}\";; go) {};; esac; done",
+            answer
+        );
+        External::spawn("sh", &["-c", &script], 4).unwrap()
+    }
+
+    #[test]
+    fn plays_the_engines_move() {
+        let mut engine = scripted("echo 'status thinking'; echo '=== f5/0.00/0.1'");
+        let game = engine.play(Game::new(), None);
+        assert_eq!(Transcript::stringify(&game.transcript), "F5");
+        assert_eq!(engine.error(), None);
+    }
+
+    #[test]
+    fn illegal_moves_forfeit() {
+        let engine = scripted("echo '=== A1'");
+        let mut contest = Contest::new(Game::new(), Box::new(engine), Box::new(FirstMove));
+        contest.play();
+
+        assert_eq!(contest.forfeit, Some(Disk::Dark));
+        assert_eq!(contest.forfeit_reason.as_deref(), Some("sh played the illegal move A1"));
+        assert!(contest.game.transcript.is_empty());
+    }
+
+    #[test]
+    fn timeouts_and_crashes() {
+        let mut slow = scripted("sleep 5");
+        slow.timeout = Duration::from_millis(200);
+        let timer = Instant::now();
+        slow.play(Game::new(), None);
+        assert!(timer.elapsed() < Duration::from_secs(2));
+        assert_eq!(slow.error(), Some("sh didn't answer in time".to_string()));
+
+        // reads the greeting and the game, and then gives up
+        let mut crashing = External::spawn("sh", &["-c", "read a; read b; read c; exit 1"], 4).unwrap();
+        crashing.play(Game::new(), None);
+        assert!(crashing.error().is_some());
+
+        assert!(External::spawn("/nonexistent/engine", &[], 4).is_err());
+    }
+}
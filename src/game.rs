@@ -1,10 +1,18 @@
-use crate::board::Board;
+use std::sync::Arc;
+
+use crate::board::{Board, SIZE};
 use crate::direction::Direction;
 use crate::disk::{Disk, DiskIter};
+use crate::layout;
+use crate::openings;
 use crate::position::{Position, PositionIter, PositionState};
+use crate::render::ansi::Ansi;
+use crate::result::GameResult;
+use crate::rules::{Rules, Supply};
 use crate::transcript::Transcript;
 
 #[derive(Clone, Debug, Ord, PartialOrd, Hash, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ValidMove {
     pub position: Position,
     pub affected: Vec<Position>,
@@ -15,6 +23,10 @@ pub struct ValidMoveIterator {
     disk: Disk,
     board: Board,
     position_iter: PositionIter,
+    // still placing the centre discs, under the rules that start that way
+    placing: bool,
+    // out of discs to play with, so nothing is playable
+    out_of_discs: bool,
 }
 
 impl ValidMoveIterator {
@@ -23,6 +35,8 @@ impl ValidMoveIterator {
             disk,
             board,
             position_iter: board.iter(),
+            placing: false,
+            out_of_discs: false,
         }
     }
 }
@@ -30,6 +44,9 @@ impl ValidMoveIterator {
 impl Iterator for ValidMoveIterator {
     type Item = ValidMove;
     fn next(&mut self) -> Option<Self::Item> {
+        if self.out_of_discs {
+            return None;
+        }
         loop {
             // work through the position iterator until we find
             // either a valid move, or we're out of available positions.
@@ -37,7 +54,7 @@ impl Iterator for ValidMoveIterator {
                 Some(position) => {
                     // given a position, we try to determine if it's a valid
                     // move; if it isn't, continue on to the next available position
-                    match Game::validate_move(&self.board, &position, self.disk) {
+                    match Game::check_move(&self.board, &position, self.disk, self.placing) {
                         Some(valid_move) => return Some(valid_move),
                         None => continue,
                     }
@@ -49,38 +66,82 @@ impl Iterator for ValidMoveIterator {
     }
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq)]
 pub struct Game {
     pub transcript: Vec<Transcript>,
     pub turn: Disk,
     board: Board,
+    rules: Rules,
+    // the layout the game started from, unless it's the usual one for the rules
+    start: Option<Arc<Board>>,
+    // discs each player has left to play, by Disk, when the rules limit them
+    supply: Option<[usize; 2]>,
 }
 
 impl Game {
     pub fn new() -> Self {
-        let mut board = Board::empty();
+        Game::with_size(SIZE)
+    }
 
-        board.set(3, 3, PositionState::Light);
-        board.set(4, 3, PositionState::Dark);
-        board.set(3, 4, PositionState::Dark);
-        board.set(4, 4, PositionState::Light);
+    // A game on a board `size` squares across, which has to be even so the four
+    // starting discs sit in the middle.
+    pub fn with_size(size: usize) -> Self {
+        Game::with_rules(size, Rules::new())
+    }
 
+    pub fn with_rules(size: usize, rules: Rules) -> Self {
+        Game {
+            transcript: Vec::new(),
+            turn: Disk::Dark,
+            board: Game::usual_layout(size, rules),
+            rules,
+            start: None,
+            supply: rules.supply.discs().map(|n| [n, n]),
+        }
+    }
+
+    // A game starting from any layout, such as one from the `layout` module, with
+    // dark to move.
+    pub fn from_layout(board: Board, rules: Rules) -> Self {
+        // odd sizes have no usual layout to compare with
+        let usual = board.size().is_multiple_of(2) && board == Game::usual_layout(board.size(), rules);
         Game {
             transcript: Vec::new(),
             turn: Disk::Dark,
             board,
+            rules,
+            start: if usual { None } else { Some(Arc::new(board)) },
+            supply: rules.supply.discs().map(|n| [n, n]),
+        }
+    }
+
+    // The standard start, or an empty centre when the rules have it placed.
+    fn usual_layout(size: usize, rules: Rules) -> Board {
+        if rules.placement {
+            Board::with_size(size)
+        } else {
+            layout::standard(size)
         }
     }
 
     pub fn from_transcript(transcript: Vec<Transcript>) -> Self {
-        let mut game = Game::new();
+        Game::from_transcript_with_size(SIZE, transcript)
+    }
+
+    pub fn from_transcript_with_size(size: usize, transcript: Vec<Transcript>) -> Self {
+        Game::with_size(size).replay(transcript)
+    }
+
+    // The game after playing the transcript's moves from this position. Passes are
+    // taken as given, and an illegal move panics; `try_replay` checks both.
+    pub fn replay(&self, transcript: Vec<Transcript>) -> Self {
+        let mut game = self.clone();
 
         for t in transcript {
             game = match t {
                 Transcript::Pass => game.pass(),
                 Transcript::Play(position) => {
-                    if let Some(valid_move) = Game::validate_move(&game.board, &position, game.turn)
-                    {
+                    if let Some(valid_move) = game.find_move(&position) {
                         game.play(valid_move)
                     } else {
                         panic!(
@@ -94,6 +155,52 @@ impl Game {
         game
     }
 
+    // The game after playing the transcript's moves from this position, or why it
+    // can't be played. With `insert_passes`, forced passes the transcript leaves out
+    // are made before its next move.
+    pub fn try_replay(&self, transcript: &[Transcript], insert_passes: bool) -> Result<Self, String> {
+        let mut game = self.clone();
+        for (ply, t) in transcript.iter().enumerate() {
+            if insert_passes && *t != Transcript::Pass && game.legal_actions() == [Transcript::Pass] {
+                game = game.pass();
+            }
+            game = game.apply(*t).map_err(|e| format!("move {}: {}", ply + 1, e))?;
+        }
+        Ok(game)
+    }
+
+    // Every move the player to move can make, a forced pass if there are none, or
+    // nothing once the game is over.
+    pub fn legal_actions(&self) -> Vec<Transcript> {
+        let moves: Vec<Transcript> = self.move_iter().map(|vm| Transcript::Play(vm.position)).collect();
+        if !moves.is_empty() || self.is_complete() {
+            moves
+        } else {
+            vec![Transcript::Pass]
+        }
+    }
+
+    // The game after a move or pass, checked against the rules; unlike `play` and
+    // `pass`, which trust the caller.
+    pub fn apply(&self, t: Transcript) -> Result<Self, String> {
+        match t {
+            Transcript::Play(position) => match self.find_move(&position) {
+                Some(vm) => Ok(self.play(vm)),
+                None if self.is_complete() => Err(format!("the game is over, so {} can't be played", t)),
+                None => Err(format!("{} isn't a legal move for {}", t, self.turn)),
+            },
+            Transcript::Pass => {
+                if self.move_iter().next().is_some() {
+                    Err(format!("{} can't pass with a move to play", self.turn))
+                } else if self.is_complete() {
+                    Err(String::from("the game is over, so there's no pass to make"))
+                } else {
+                    Ok(self.pass())
+                }
+            }
+        }
+    }
+
     pub fn play(&self, vm: ValidMove) -> Self {
         // Operate on a copy, keeping self immutable.
         let mut g = self.clone();
@@ -104,6 +211,16 @@ impl Game {
             g.board.flip(flip.x, flip.y);
         }
 
+        // the disc comes from the player's own supply, or the opponent's once theirs is gone
+        if let Some(supply) = &mut g.supply {
+            let (own, other) = (g.turn as usize, g.turn.opposite() as usize);
+            if supply[own] > 0 {
+                supply[own] -= 1;
+            } else {
+                supply[other] -= 1;
+            }
+        }
+
         g.transcript.push(vm.position.into());
         Game::end_turn(g)
     }
@@ -117,14 +234,7 @@ impl Game {
 
     // Determines whether a grid can be played by either Light or Dark
     pub fn is_complete(&self) -> bool {
-        for p in PositionIter::new() {
-            for d in DiskIter::new() {
-                if Game::validate_move(&self.board, &p, d).is_some() {
-                    return false;
-                }
-            }
-        }
-        true
+        DiskIter::new().all(|d| self.moves_for(d).next().is_none())
     }
 
     pub fn score(&self) -> (usize, usize) {
@@ -132,7 +242,7 @@ impl Game {
         let mut light_score = 0;
         for p in self.board.iter() {
             match self.board.get(p.x, p.y) {
-                PositionState::Empty => continue,
+                PositionState::Empty | PositionState::Blocked => continue,
                 PositionState::Dark => dark_score += 1,
                 PositionState::Light => light_score += 1,
             }
@@ -140,15 +250,118 @@ impl Game {
         (dark_score, light_score)
     }
 
+    // How the game came out, once neither player can move.
+    pub fn result(&self) -> Option<GameResult> {
+        if !self.is_complete() {
+            return None;
+        }
+        Some(GameResult::new(self.score(), self.board.empties(), self.rules))
+    }
+
     pub fn move_iter(&self) -> ValidMoveIterator {
-        ValidMoveIterator::new(self.board, self.turn)
+        self.moves_for(self.turn)
+    }
+
+    // The moves either player could make here, whoever's turn it is.
+    pub fn moves_for(&self, disk: Disk) -> ValidMoveIterator {
+        let mut moves = ValidMoveIterator::new(self.board, disk);
+        moves.placing = self.is_placing();
+        moves.out_of_discs = !self.has_discs(disk);
+        moves
+    }
+
+    // Discs left to play for each player, Dark's first, when the rules limit them.
+    pub fn supply(&self) -> Option<(usize, usize)> {
+        self.supply.map(|[dark, light]| (dark, light))
+    }
+
+    // whether the player has a disc to play, of their own or handed over
+    fn has_discs(&self, disk: Disk) -> bool {
+        match (self.supply, self.rules.supply) {
+            (Some(supply), Supply::HandOver(_)) => supply[0] + supply[1] > 0,
+            (Some(supply), _) => supply[disk as usize] > 0,
+            (None, _) => true,
+        }
+    }
+
+    // whether the centre discs are still being placed
+    fn is_placing(&self) -> bool {
+        let size = self.board.size();
+        self.rules.placement && layout::centre(size).iter().any(|p| self.board.get(p.x, p.y) == PositionState::Empty)
+    }
+
+    fn find_move(&self, position: &Position) -> Option<ValidMove> {
+        if !self.has_discs(self.turn) {
+            return None;
+        }
+        Game::check_move(&self.board, position, self.turn, self.is_placing())
     }
 
     pub fn valid_moves(&self) -> Vec<ValidMove> {
         self.move_iter().collect()
     }
 
+    pub fn board(&self) -> &Board {
+        &self.board
+    }
+
+    pub fn rules(&self) -> Rules {
+        self.rules
+    }
+
+    // A new game from the same starting position, under the same rules.
+    pub fn restart(&self) -> Game {
+        match &self.start {
+            None => Game::with_rules(self.board.size(), self.rules),
+            Some(start) => Game {
+                transcript: Vec::new(),
+                turn: Disk::Dark,
+                board: **start,
+                rules: self.rules,
+                start: Some(start.clone()),
+                supply: self.rules.supply.discs().map(|n| [n, n]),
+            },
+        }
+    }
+
+    // The board the game started from.
+    pub fn layout(&self) -> Board {
+        match &self.start {
+            None => Game::usual_layout(self.board.size(), self.rules),
+            Some(start) => **start,
+        }
+    }
+
+    pub fn has_standard_layout(&self) -> bool {
+        self.start.is_none()
+    }
+
+    // The most recent play and the discs it flipped, recovered by replaying
+    // the transcript. None if nothing has been played yet, or the last turn was a pass.
+    pub fn last_move(&self) -> Option<ValidMove> {
+        let (last, previous) = self.transcript.split_last()?;
+        match last {
+            Transcript::Pass => None,
+            Transcript::Play(position) => {
+                let before = self.restart().replay(previous.to_vec());
+                before.find_move(position)
+            }
+        }
+    }
+
+    // The name of the opening this game was played from, if it's a known one.
+    pub fn opening_name(&self) -> Option<&'static str> {
+        if self.board.size() != SIZE || !self.has_standard_layout() || self.rules.placement {
+            return None;
+        }
+        openings::name(&self.transcript)
+    }
+
     pub fn pp(&self) {
+        self.pp_with(&Ansi::for_stdout());
+    }
+
+    pub fn pp_with(&self, renderer: &Ansi) {
         let (dark_score, light_score) = self.score();
         let next_turn = if self.is_complete() {
             "Complete".to_string()
@@ -156,10 +369,30 @@ impl Game {
             format!("{}", self.turn)
         };
 
-        println!("{}", self.board.to_string());
+        println!("{}", renderer.render(self));
+        if !self.rules.is_standard() {
+            println!("Rules: {}", self.rules);
+        }
+        if !self.has_standard_layout() {
+            println!("Layout: {}", layout::format(&self.layout()));
+        }
         println!("Transcript: {}", Transcript::stringify(&self.transcript));
+        if let Some(name) = self.opening_name() {
+            println!("Opening: {}", name);
+        }
         println!("Score: Dark {}, Light {}", dark_score, light_score);
+        if let Some((dark, light)) = self.supply() {
+            println!("Discs left: Dark {}, Light {}", dark, light);
+        }
+        println!(
+            "Stable: Dark {}, Light {}",
+            self.board.stable_discs(Disk::Dark).len(),
+            self.board.stable_discs(Disk::Light).len()
+        );
         println!("Next turn: {}", next_turn);
+        if let Some(result) = self.result() {
+            println!("Result: {}", result);
+        }
     }
 
     fn end_turn(mut game: Game) -> Self {
@@ -167,12 +400,32 @@ impl Game {
         game
     }
 
+    // A move as the rules have it: while the centre is being placed, any empty
+    // centre square can be played, flipping nothing.
+    fn check_move(board: &Board, position: &Position, disk: Disk, placing: bool) -> Option<ValidMove> {
+        if !placing {
+            return Game::validate_move(board, position, disk);
+        }
+        let centre = layout::centre(board.size());
+        if centre.contains(position) && board.get(position.x, position.y) == PositionState::Empty {
+            Some(ValidMove {
+                position: *position,
+                affected: Vec::new(),
+            })
+        } else {
+            None
+        }
+    }
+
     // Determines whether a given position can be played, and what it's effect will be.
     fn validate_move(board: &Board, position: &Position, disk: Disk) -> Option<ValidMove> {
         // println!("validate_move {:?} for {}", position, disk);
         // println!("{}", board);
 
-        // before anything else, the position must be empty.
+        // before anything else, the position must be on the board, and empty.
+        if position.x >= board.size() || position.y >= board.size() {
+            return None;
+        }
         if board.get(position.x, position.y) != PositionState::Empty {
             // println!("Position isn't empty!");
             return None;
@@ -191,12 +444,12 @@ impl Game {
 
             // loop through neighbors to see if we can find anything
             loop {
-                match current_position.neighbor(direction) {
+                match current_position.neighbor(direction, board.size()) {
                     None => break, // no neighbor in that direction, move on to the next one
                     Some(new_position) => {
                         let np_state = board.get(new_position.x, new_position.y);
 
-                        if np_state == PositionState::Empty {
+                        if np_state == PositionState::Empty || np_state == PositionState::Blocked {
                             // womp womp, no luck, break and find a new direction!
                             break;
                         }
@@ -231,6 +484,12 @@ impl Game {
     }
 }
 
+impl Default for Game {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -238,6 +497,7 @@ mod tests {
     use std::mem;
 
     #[test]
+    #[allow(clippy::bool_assert_comparison)]
     fn validate_positions() {
         let g = Game::new();
 
@@ -273,6 +533,7 @@ mod tests {
     }
 
     #[test]
+    #[allow(clippy::bool_assert_comparison)]
     fn completion() {
         let mut g = Game::new();
         assert_eq!(g.is_complete(), false);
@@ -294,6 +555,157 @@ mod tests {
         assert_eq!(light_score, 0);
     }
 
+    #[test]
+    fn last_move() {
+        let mut g = Game::new();
+        assert_eq!(g.last_move(), None);
+
+        g = Game::from_transcript(Transcript::from_string("D3"));
+        let vm = g.last_move().unwrap();
+        assert_eq!(vm.position, Position { x: 3, y: 2 });
+        assert_eq!(vm.affected, vec![Position { x: 3, y: 3 }]);
+
+        g = g.pass();
+        assert_eq!(g.last_move(), None);
+    }
+
+    #[test]
+    fn opening_name() {
+        assert_eq!(Game::new().opening_name(), None);
+        let g = Game::from_transcript(Transcript::from_string("E6F4C3C4D3"));
+        assert_eq!(g.opening_name(), Some("Tiger"));
+    }
+
+    #[test]
+    fn sizes() {
+        let g = Game::with_size(6);
+        assert_eq!(g.score(), (2, 2));
+        assert_eq!(g.board().get(2, 2), PositionState::Light);
+        assert_eq!(g.valid_moves().len(), 4);
+
+        // squares past the edge of a small board can't be played
+        let g = Game::from_transcript_with_size(6, Transcript::from_string("C2"));
+        assert!(Game::validate_move(g.board(), &Position { x: 6, y: 6 }, g.turn).is_none());
+        assert_eq!(g.last_move().unwrap().affected, vec![Position { x: 2, y: 2 }]);
+
+        let g = Game::from_transcript_with_size(10, Transcript::from_string("G6"));
+        assert_eq!(g.score(), (4, 1));
+    }
+
+    #[test]
+    fn rules() {
+        let g = Game::with_rules(8, Rules::anti()).replay(Transcript::from_string("F5D6"));
+        assert_eq!(g.rules(), Rules::anti());
+        assert_eq!(g.restart().rules(), Rules::anti());
+        assert!(g.restart().transcript.is_empty());
+        assert_eq!(g.last_move().unwrap().position, Position { x: 3, y: 5 });
+    }
+
+    #[test]
+    fn layouts() {
+        // a hole next to the centre takes away a move, and stops a line from flipping
+        let mut board = layout::standard(8);
+        board.set(3, 2, PositionState::Blocked);
+        let g = Game::from_layout(board, Rules::new());
+        assert_eq!(g.valid_moves().len(), 3);
+        assert!(!g.has_standard_layout());
+
+        let g = g.replay(Transcript::from_string("F5"));
+        assert_eq!(g.restart().board(), &board);
+        assert_eq!(g.layout(), board);
+        assert_eq!(g.last_move().unwrap().affected, vec![Position { x: 4, y: 4 }]);
+
+        let mut line = Board::with_size(4);
+        line.set(0, 0, PositionState::Dark);
+        line.set(1, 0, PositionState::Light);
+        line.set(2, 0, PositionState::Blocked);
+        line.set(1, 1, PositionState::Light);
+        let g = Game::from_layout(line, Rules::new());
+        assert!(g.valid_moves().iter().all(|vm| vm.position != Position { x: 3, y: 0 }));
+        assert_eq!(g.score(), (1, 2));
+
+        assert!(Game::from_layout(layout::standard(6), Rules::new()).has_standard_layout());
+
+        // odd sizes have no standard start, but any layout of them can be played
+        let odd = Board::from_rows(&[".....", ".LD..", ".DL..", ".....", "....."]).unwrap();
+        let g = Game::from_layout(odd, Rules::new());
+        assert!(!g.has_standard_layout());
+        assert_eq!(g.valid_moves().len(), 4);
+        let g = g.replay(Transcript::from_string("B1"));
+        assert_eq!(g.restart().board(), &odd);
+        assert_eq!(g.layout(), odd);
+    }
+
+    #[test]
+    fn historical_rules() {
+        // the first four moves fill the centre, in any order and flipping nothing
+        let g = Game::with_rules(8, Rules::historical());
+        assert_eq!(g.score(), (0, 0));
+        assert_eq!(g.valid_moves().len(), 4);
+        assert!(g.valid_moves().iter().all(|vm| vm.affected.is_empty()));
+        assert!(g.find_move(&Position { x: 5, y: 4 }).is_none());
+
+        let g = g.replay(Transcript::from_string("D4E4D5"));
+        assert_eq!(g.valid_moves().len(), 1);
+        assert!(g.last_move().unwrap().affected.is_empty());
+        let g = g.replay(Transcript::from_string("E5"));
+        assert_eq!(g.score(), (2, 2));
+        assert_eq!(g.supply(), Some((30, 30)));
+        assert!(g.valid_moves().iter().all(|vm| !vm.affected.is_empty()));
+        assert!(g.has_standard_layout());
+        assert_eq!(g.opening_name(), None);
+        assert_eq!(g.restart().board(), &Board::with_size(8));
+
+        // out of discs, a player either passes or plays one of the opponent's
+        let rules = |supply| Rules { supply, ..Rules::new() };
+        let g = Game::with_rules(8, rules(Supply::Pass(1))).replay(Transcript::from_string("F5PP"));
+        assert_eq!(g.supply(), Some((0, 1)));
+        assert!(g.valid_moves().is_empty());
+        assert!(!g.is_complete());
+        let g = Game::with_rules(8, rules(Supply::HandOver(1))).replay(Transcript::from_string("F5PP"));
+        let g = g.replay(Transcript::from_string("C3"));
+        assert_eq!(g.supply(), Some((0, 0)));
+        assert!(g.is_complete());
+        assert_eq!(Game::new().supply(), None);
+    }
+
+    #[test]
+    fn actions() {
+        let g = Game::new();
+        assert_eq!(g.legal_actions().len(), 4);
+        assert!(g.apply(Transcript::Pass).is_err());
+        assert!(g.apply(Transcript::from_string("A1")[0]).is_err());
+        assert!(g.apply(Transcript::from_string("F5")[0]).is_ok());
+
+        // passes are forced after E8 and F7, and the game is over after G8
+        let played = "C4E3F4G5G4G3E2C3H6C5B4F3H5B3H3A5A4F2G2B5A6H2B6H1H4E1G1B7D1H7C6A7A2F1B8D3A8F5C2B2A3D6E7C1B1C7D8A1F6D2G6F8D7G7E6C8E8";
+        let g = Game::from_transcript(Transcript::from_string(played));
+        assert_eq!(g.legal_actions(), vec![Transcript::Pass]);
+        assert!(g.apply(Transcript::from_string("F7")[0]).is_err());
+
+        let without_passes = Transcript::from_string(&format!("{}F7H8G8", played));
+        assert!(g.restart().try_replay(&without_passes, false).is_err());
+        let g = Game::new().try_replay(&without_passes, true).unwrap();
+        assert_eq!(Transcript::stringify(&g.transcript), format!("{}PPF7PPH8G8", played));
+        assert!(g.legal_actions().is_empty());
+        assert!(g.apply(Transcript::Pass).is_err());
+    }
+
+    #[test]
+    fn results() {
+        assert_eq!(Game::new().result(), None);
+
+        // a wipeout leaves the board mostly empty, and the empties go to the winner
+        let g = Game::from_transcript(Transcript::from_string(MANUBU_MARUO));
+        let result = g.result().unwrap();
+        assert_eq!(result.winner, Some(Disk::Dark));
+        assert_eq!(result.differential, 13);
+        assert_eq!(result.score, (64, 0));
+
+        let anti = Game::with_rules(8, Rules::anti()).replay(g.transcript.clone());
+        assert_eq!(anti.result().unwrap().winner, Some(Disk::Light));
+    }
+
     #[test]
     fn mem_size() {
         let g = Game::from_transcript(Transcript::from_string(MANUBU_MARUO));
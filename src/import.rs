@@ -0,0 +1,263 @@
+use std::fmt;
+
+use crate::game::Game;
+use crate::position::Position;
+use crate::transcript::Transcript;
+
+// Reads game records as people write them, not just as `Transcript::from_string`
+// does: any case, with separators and move numbers, passes written out or left
+// out, and in any orientation of the board. What had to be changed to read a
+// record is reported back, so it can be checked.
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Normalization {
+    // moves like "f5"
+    Lowercase,
+    // spaces, commas and the like between moves
+    Separators,
+    // "1. f5 2. d6"
+    MoveNumbers,
+    // passes written as "pass", "PA" or "--" rather than "PP"
+    PassNotation,
+    // forced passes the record left out
+    InsertedPasses(usize),
+    // passes recorded after the game was over
+    DroppedPasses(usize),
+    // the record only replays when reflected or rotated, as when it was written for
+    // the other starting diagonal or turned to start with F5
+    Reoriented,
+}
+
+impl fmt::Display for Normalization {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Normalization::Lowercase => write!(f, "moves in lowercase"),
+            Normalization::Separators => write!(f, "separators between moves"),
+            Normalization::MoveNumbers => write!(f, "move numbers"),
+            Normalization::PassNotation => write!(f, "passes written as pass, PA or --"),
+            Normalization::InsertedPasses(n) => write!(f, "{} forced pass{} inserted", n, plural(*n)),
+            Normalization::DroppedPasses(n) => write!(f, "{} pass{} after the end dropped", n, plural(*n)),
+            Normalization::Reoriented => write!(f, "board reoriented"),
+        }
+    }
+}
+
+fn plural(n: usize) -> &'static str {
+    if n == 1 {
+        ""
+    } else {
+        "es"
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Import {
+    pub transcript: Vec<Transcript>,
+    pub normalizations: Vec<Normalization>,
+}
+
+// Reads a record of a game played from `start`, which the moves are replayed
+// through to place passes and pick the board's orientation.
+pub fn parse(text: &str, start: &Game) -> Result<Import, String> {
+    let (moves, mut normalizations) = tokenize(text)?;
+
+    // the record as written, then each reflection and rotation of it
+    let size = start.board().size();
+    let off_board = moves.iter().position(|t| matches!(t, Transcript::Play(p) if p.x >= size || p.y >= size));
+    if let Some(ply) = off_board {
+        return Err(format!("move {}: off the board", ply + 1));
+    }
+
+    let mut first_error = None;
+    for (i, symmetry) in Position::SYMMETRIES.iter().enumerate() {
+        let oriented: Vec<Transcript> = moves
+            .iter()
+            .map(|t| match t {
+                Transcript::Play(p) => Transcript::Play(symmetry(*p, size)),
+                Transcript::Pass => Transcript::Pass,
+            })
+            .collect();
+
+        match replay(&oriented, start) {
+            Ok((transcript, passes)) => {
+                if i > 0 {
+                    normalizations.push(Normalization::Reoriented);
+                }
+                normalizations.extend(passes);
+                return Ok(Import { transcript, normalizations });
+            }
+            Err(e) => {
+                first_error.get_or_insert(e);
+            }
+        }
+    }
+    Err(first_error.unwrap_or_default())
+}
+
+// Splits the record into moves and passes, noting the notation it's written in.
+fn tokenize(text: &str) -> Result<(Vec<Transcript>, Vec<Normalization>), String> {
+    let chars: Vec<char> = text.trim().chars().collect();
+    let mut moves = Vec::new();
+    let mut found = Vec::new();
+    let mut note = |n: Normalization| {
+        if !found.contains(&n) {
+            found.push(n);
+        }
+    };
+
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        let rest: String = chars[i..].iter().take(4).collect::<String>().to_ascii_lowercase();
+
+        if rest.starts_with("--") {
+            note(Normalization::PassNotation);
+            moves.push(Transcript::Pass);
+            i += 2;
+        } else if c.is_whitespace() || ",;:|/.-".contains(c) {
+            note(Normalization::Separators);
+            i += 1;
+        } else if rest.starts_with("pass") {
+            note(Normalization::PassNotation);
+            moves.push(Transcript::Pass);
+            i += 4;
+        } else if rest.starts_with("pa") || rest.starts_with("pp") {
+            if rest.starts_with("pa") {
+                note(Normalization::PassNotation);
+            }
+            if chars[i..i + 2].iter().any(|c| c.is_lowercase()) {
+                note(Normalization::Lowercase);
+            }
+            moves.push(Transcript::Pass);
+            i += 2;
+        } else if c.is_ascii_digit() {
+            // a move number, with its trailing dots or parenthesis
+            note(Normalization::MoveNumbers);
+            while i < chars.len() && (chars[i].is_ascii_digit() || chars[i] == '.' || chars[i] == ')') {
+                i += 1;
+            }
+        } else if ('a'..='p').contains(&c.to_ascii_lowercase()) && chars.get(i + 1).is_some_and(|d| d.is_ascii_digit()) {
+            if c.is_lowercase() {
+                note(Normalization::Lowercase);
+            }
+            let mut end = i + 1;
+            while end < chars.len() && chars[end].is_ascii_digit() {
+                end += 1;
+            }
+            let word: String = chars[i..end].iter().collect();
+            let y: usize = word[1..].parse().map_err(|_| format!("invalid move {}", word))?;
+            if y == 0 {
+                return Err(format!("invalid move {}", word));
+            }
+            moves.push(Transcript::Play(Position {
+                x: c.to_ascii_lowercase() as usize - 'a' as usize,
+                y: y - 1,
+            }));
+            i = end;
+        } else {
+            return Err(format!("unexpected '{}' at character {}", c, i + 1));
+        }
+    }
+    Ok((moves, found))
+}
+
+// The moves as played from `start`, with forced passes filled in and passes after
+// the end left out, and how many of each there were.
+fn replay(moves: &[Transcript], start: &Game) -> Result<(Vec<Transcript>, Vec<Normalization>), String> {
+    let mut game = start.clone();
+    let (mut inserted, mut dropped) = (0, 0);
+
+    for (ply, t) in moves.iter().enumerate() {
+        let actions = game.legal_actions();
+        if *t == Transcript::Pass && actions.is_empty() {
+            dropped += 1;
+            continue;
+        }
+        if *t != Transcript::Pass && actions == [Transcript::Pass] {
+            game = game.pass();
+            inserted += 1;
+        }
+        game = game.apply(*t).map_err(|e| format!("move {}: {}", ply + 1, e))?;
+    }
+
+    let mut normalizations = Vec::new();
+    if inserted > 0 {
+        normalizations.push(Normalization::InsertedPasses(inserted));
+    }
+    if dropped > 0 {
+        normalizations.push(Normalization::DroppedPasses(dropped));
+    }
+    Ok((game.transcript[start.transcript.len()..].to_vec(), normalizations))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transcript::MANUBU_MARUO;
+
+    #[test]
+    fn notations() {
+        let start = Game::new();
+        let plain = Transcript::from_string("F5D6C3");
+
+        let read = parse("F5D6C3", &start).unwrap();
+        assert_eq!(read.transcript, plain);
+        assert!(read.normalizations.is_empty());
+
+        let read = parse("f5d6c3", &start).unwrap();
+        assert_eq!(read.transcript, plain);
+        assert_eq!(read.normalizations, vec![Normalization::Lowercase]);
+
+        let read = parse("1. f5 2. d6 3) c3", &start).unwrap();
+        assert_eq!(read.transcript, plain);
+        assert_eq!(
+            read.normalizations,
+            vec![Normalization::MoveNumbers, Normalization::Separators, Normalization::Lowercase]
+        );
+
+        assert!(parse("F5 D6 X9", &start).is_err());
+        assert!(parse("F5F5", &start).is_err());
+        assert!(parse("I1", &start).is_err());
+        assert!(parse("F5 P9", &start).is_err());
+    }
+
+    #[test]
+    fn passes() {
+        // passes are forced after E8 and F7, and the game is over after G8
+        let played = "C4E3F4G5G4G3E2C3H6C5B4F3H5B3H3A5A4F2G2B5A6H2B6H1H4E1G1B7D1H7C6A7A2F1B8D3A8F5C2B2A3D6E7C1B1C7D8A1F6D2G6F8D7G7E6C8E8";
+        let full = Transcript::from_string(&format!("{}PPF7PPH8G8", played));
+
+        let read = parse(&format!("{}F7H8G8", played), &Game::new()).unwrap();
+        assert_eq!(read.transcript, full);
+        assert_eq!(read.normalizations, vec![Normalization::InsertedPasses(2)]);
+
+        let read = parse(&format!("{} pass F7 -- H8 G8 PA", played), &Game::new()).unwrap();
+        assert_eq!(read.transcript, full);
+        assert!(read.normalizations.contains(&Normalization::PassNotation));
+        assert!(read.normalizations.contains(&Normalization::DroppedPasses(1)));
+        assert_eq!(Normalization::DroppedPasses(1).to_string(), "1 pass after the end dropped");
+
+        // a pass when there's a move to make is a mistake in the record
+        assert!(parse("F5PP", &Game::new()).is_err());
+    }
+
+    #[test]
+    fn orientations() {
+        // the same game reflected, as written for a board with the other starting
+        // diagonal; reflected back, it's the one on record
+        let mirrored: Vec<Transcript> = Transcript::from_string(MANUBU_MARUO)
+            .into_iter()
+            .map(|t| match t {
+                Transcript::Play(p) => Transcript::Play(Position::mirror(p, 8)),
+                Transcript::Pass => t,
+            })
+            .collect();
+        let text = Transcript::stringify(&mirrored);
+        assert!(Game::new().try_replay(&mirrored, false).is_err());
+
+        let read = parse(&text, &Game::new()).unwrap();
+        assert_eq!(read.normalizations, vec![Normalization::Reoriented]);
+        let game = Game::new().replay(read.transcript.clone());
+        assert_eq!(game.score(), Game::from_transcript(Transcript::from_string(MANUBU_MARUO)).score());
+    }
+}
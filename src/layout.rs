@@ -0,0 +1,111 @@
+use rand::seq::SliceRandom;
+use rand::Rng;
+
+use crate::board::Board;
+use crate::position::{Position, PositionState};
+
+// Starting positions. A layout is just the board a game starts from, which can
+// have any discs on it and blocked squares that are never played.
+
+// the four centre squares, top left first, of an even sized board
+pub(crate) fn centre(size: usize) -> [Position; 4] {
+    let (low, high) = (size / 2 - 1, size / 2);
+    [
+        Position { x: low, y: low },
+        Position { x: high, y: low },
+        Position { x: low, y: high },
+        Position { x: high, y: high },
+    ]
+}
+
+// The usual start, with the centre discs on the diagonals.
+pub fn standard(size: usize) -> Board {
+    assert!(size.is_multiple_of(2), "board size must be even");
+    let mut board = Board::with_size(size);
+
+    let states = [PositionState::Light, PositionState::Dark, PositionState::Dark, PositionState::Light];
+    for (p, state) in centre(size).iter().zip(states) {
+        board.set(p.x, p.y, state);
+    }
+    board
+}
+
+// The cross start, also called parallel, with each color's two discs side by side.
+pub fn cross(size: usize) -> Board {
+    assert!(size.is_multiple_of(2), "board size must be even");
+    let mut board = Board::with_size(size);
+
+    let states = [PositionState::Light, PositionState::Light, PositionState::Dark, PositionState::Dark];
+    for (p, state) in centre(size).iter().zip(states) {
+        board.set(p.x, p.y, state);
+    }
+    board
+}
+
+// A randomized start: the centre discs in any of their six arrangements with two of
+// each color, and `blocked` squares outside the centre blocked at random.
+pub fn random<R: Rng>(size: usize, blocked: usize, rng: &mut R) -> Board {
+    assert!(size.is_multiple_of(2), "board size must be even");
+    let mut board = Board::with_size(size);
+
+    let mut states = [PositionState::Dark, PositionState::Dark, PositionState::Light, PositionState::Light];
+    states.shuffle(rng);
+    let centre = centre(size);
+    for (p, state) in centre.iter().zip(states) {
+        board.set(p.x, p.y, state);
+    }
+
+    let mut outside: Vec<Position> = board.iter().filter(|p| !centre.contains(p)).collect();
+    outside.shuffle(rng);
+    for p in outside.iter().take(blocked) {
+        board.set(p.x, p.y, PositionState::Blocked);
+    }
+    board
+}
+
+// Layouts are written as their rows from the top separated by slashes, as in
+// `Board::rows`; `standard` and `cross` name those starts on the standard board.
+pub fn parse(text: &str) -> Option<Board> {
+    match text {
+        "standard" => Some(standard(8)),
+        "cross" => Some(cross(8)),
+        rows => Board::from_rows(&rows.split('/').collect::<Vec<_>>()),
+    }
+}
+
+pub fn format(board: &Board) -> String {
+    board.rows().join("/")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::solvers::random::Seed;
+
+    #[test]
+    fn starts() {
+        let cross = cross(8);
+        assert_eq!(cross.get(3, 3), PositionState::Light);
+        assert_eq!(cross.get(4, 3), PositionState::Light);
+        assert_eq!(cross.get(3, 4), PositionState::Dark);
+
+        let random = random(8, 6, &mut Seed::from_string("layout".to_string()).rng());
+        let count = |state| random.iter().filter(|p| random.get(p.x, p.y) == state).count();
+        assert_eq!(count(PositionState::Blocked), 6);
+        assert_eq!(count(PositionState::Dark), 2);
+        assert_eq!(count(PositionState::Light), 2);
+    }
+
+    #[test]
+    fn notation() {
+        assert_eq!(parse("cross"), Some(cross(8)));
+        assert_eq!(parse(&format(&standard(6))), Some(standard(6)));
+        assert_eq!(format(&standard(4)), "..../.LD./.DL./....");
+        assert_eq!(parse("#.../.LD./.DL./...."), {
+            let mut b = standard(4);
+            b.set(0, 0, PositionState::Blocked);
+            Some(b)
+        });
+        assert_eq!(parse("..../.LD."), None);
+    }
+}
@@ -0,0 +1,38 @@
+// Reversi: the rules of the game, transcripts, exhaustive and random game
+// generators, static evaluation and search, matches between players, opening books
+// and renderers. The `reversi` binary is a command line front end over all of it.
+
+pub mod analysis;
+pub mod board;
+pub mod book;
+pub mod clock;
+pub mod collector;
+pub mod contest;
+pub mod direction;
+pub mod disk;
+pub mod eval;
+pub mod external;
+pub mod game;
+pub mod import;
+pub mod layout;
+pub mod nboard;
+pub mod openings;
+pub mod position;
+pub mod render;
+pub mod result;
+pub mod rules;
+#[cfg(feature = "serde")]
+pub mod serialization;
+pub mod solvers;
+pub mod suite;
+pub mod transcript;
+
+// the types most programs need
+pub use crate::board::Board;
+pub use crate::contest::{Contest, Player};
+pub use crate::disk::Disk;
+pub use crate::game::{Game, ValidMove};
+pub use crate::position::Position;
+pub use crate::result::GameResult;
+pub use crate::rules::Rules;
+pub use crate::transcript::Transcript;
@@ -1,38 +1,71 @@
-mod board;
-mod collector;
-mod contest;
-mod direction;
-mod disk;
-mod game;
-mod position;
-mod solvers;
-mod transcript;
-
-extern crate rand;
-extern crate sha2;
-extern crate rayon;
-
+use rand::prelude::*;
 use rayon::prelude::*;
 
-use crate::contest::{Contest, Player};
-use crate::collector::Collector;
-use crate::game::Game;
-use crate::solvers::incremental::Incremental;
-use crate::solvers::random::{Random, Seed};
-use crate::transcript::{Transcript, MANUBU_MARUO};
-
+use reversi::analysis::{self, Analyzer};
+use reversi::board::{Board, MAX_SIZE, MIN_SIZE, SIZE};
+use reversi::book::Book;
+use reversi::clock::Clock;
+use reversi::collector::{Collector, Counter, Printer};
+use reversi::contest::{Booked, Contest, Engine, FirstMove, Player};
+use reversi::disk::Disk;
+use reversi::eval::heuristic::{Heuristic, Parity};
+use reversi::eval::pattern::Pattern;
+use reversi::eval::training::Trainer;
+use reversi::eval::{final_score, Evaluator, DISC};
+use reversi::external::External;
+use reversi::game::Game;
+use reversi::layout;
+use reversi::nboard::{to_annotated_ggf, NBoard};
+use reversi::position::Position;
+use reversi::render::gif::Gif;
+use reversi::render::html::Html;
+use reversi::render::svg::Svg;
+use reversi::result::Record;
+use reversi::rules::{Rules, Supply};
+use reversi::solvers::alphabeta::AlphaBeta;
+use reversi::solvers::deepening::Deepening;
+use reversi::solvers::incremental::Incremental;
+use reversi::solvers::random::{Random, Seed};
+use reversi::solvers::smp::Smp;
+use reversi::solvers::table::TranspositionTable;
+use reversi::suite::Suite;
+use reversi::transcript::{Transcript, MANUBU_MARUO};
+
+use std::collections::HashMap;
 use std::env;
-use std::time::Instant;
+use std::fs::File;
+use std::io::{BufRead, BufReader, Read, Write};
 use std::sync::mpsc::Sender;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 
 fn main() {
-    match env::args().last() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    match args.first() {
         None => help(),
         Some(raw_mode) => match raw_mode.to_ascii_lowercase().trim() {
             "demos" => demos(),
             "generate" => generate(),
-            "benchmark" => benchmark(),
-            "random" => random(),
+            "benchmark" => match args.get(1).map(|a| a.as_str()) {
+                Some("search") => benchmark_search(&args[2..]),
+                _ => benchmark(),
+            },
+            "random" => random(&args[1..]),
+            "svg" => svg(&args[1..]),
+            "gif" => gif(&args[1..]),
+            "export-html" => export_html(&args[1..]),
+            "search" => search(&args[1..]),
+            "analyze" => analyze(&args[1..]),
+            "train" => train(&args[1..]),
+            "match" => play_match(&args[1..]),
+            "openings" => opening_report(&args[1..]),
+            "import" => import(&args[1..]),
+            "nboard" => nboard(&args[1..]),
+            "book" => match args.get(1).map(|a| a.as_str()) {
+                Some("build") => build_book(&args[2..]),
+                Some("lookup") => lookup_book(&args[2..]),
+                _ => help(),
+            },
             _ => help(),
         },
     }
@@ -46,18 +79,165 @@ fn help() {
     println!("  demos            Spits out a series of demos and benchmarking info.");
     println!("  generate         Prints non-repeating transcripts.");
     println!("  benchmark        How fast can I generate games? 😅");
+    println!("  benchmark search How much faster is the parallel searcher?");
+    println!("    --depth <plies>  Depth to search to (default 8).");
+    println!("    --threads <n>    Threads for the parallel searcher (default: all cores).");
     println!("  random           Generates random transcripts.");
+    println!("    --board-size <n> Plays on an n by n board, for any even n from 4 to 16 (default 8).");
+    println!("                     Also for svg, gif, export-html, search, and match without openings.");
+    println!("    --anti           Plays anti-reversi, where the fewest discs win; also for search and match.");
+    println!("    --placement      Starts with an empty centre, filled by the first four moves without flipping.");
+    println!("    --discs <n>      Gives each player n discs (32 historically); out of discs, a player passes,");
+    println!("                     or with --hand-over is given the opponent's; as for --anti.");
+    println!("    --layout <layout>");
+    println!("                     Starts from standard, cross, random[:<blocked squares>] (with --seed <text>),");
+    println!("                     or rows with . D L and # for blocked, like ..../.LD#/.DL./....; as for --board-size.");
+    println!("  svg <transcript> Prints an SVG diagram of the game.");
+    println!("    --kifu           Numbers every move over the starting position.");
+    println!("    --highlight <squares>  Highlights squares, e.g. C5F4.");
+    println!("  gif <transcript> Writes an animated GIF replay of the game to stdout.");
+    println!("    --delay <cs>     Frame delay in hundredths of a second (default 60).");
+    println!("    --size <px>      Size of each square in pixels (default 32).");
+    println!("    --no-flips       Skip the frame showing each move before its flips.");
+    println!("  export-html <transcript>");
+    println!("                   Writes a self-contained HTML viewer for the game to stdout.");
+    println!("    --title <text>   Page title.");
+    println!("    --eval <depth>   Adds an engine evaluation of every move.");
+    println!("  search <transcript>");
+    println!("                   Searches for the best move in the position.");
+    println!("    --depth <plies>  Search depth (default 6).");
+    println!("    --evaluator <name>");
+    println!("                     heuristic (default), pattern or parity.");
+    println!("    --weights <file> Pattern weights for the pattern evaluator.");
+    println!("    --time <ms>      Search with iterative deepening for this long instead.");
+    println!("    --infinite       Search with iterative deepening until enter is pressed.");
+    println!("    --threads <n>    Search on this many threads with --time or --infinite (default 1).");
+    println!("  analyze <transcript>");
+    println!("                   Finds the best move at every ply and grades each move played as best,");
+    println!("                   inaccuracy, mistake or blunder by how much it gives away. The game can be");
+    println!("                   written any way import reads.");
+    println!("    --depth <plies>  Search depth (default 6).");
+    println!("    --exact <empties>");
+    println!("                     Solves positions with this many empty squares exactly (default 12).");
+    println!("    --evaluator <name>, --weights <file>");
+    println!("                     As for search.");
+    println!("    --format <format>");
+    println!("                     text (default), json, or ggf with each move's evaluation.");
+    println!("  match            Plays two players against each other.");
+    println!("    --dark <player>, --light <player>");
+    println!("                     first, heuristic (default), parity, pattern:<weights file>, or");
+    println!("                     engine:<command> to run an NBoard protocol engine, searching 8 plies.");
+    println!("    --clock <secs>   Time per player for the whole game (default 60).");
+    println!("    --increment <secs>");
+    println!("                     Time added after every move (default 0).");
+    println!("    --suite <file>   Plays both sides of every opening in the file, e.g. the XOT list.");
+    println!("    --random-openings <n>");
+    println!("                     Plays both sides of n random openings that are close to even ...");
+    println!("    --plies <n>      ... of this many moves (default 8) ...");
+    println!("    --window <discs> ... scoring within this many discs of zero (default 2).");
+    println!("    --book <file>    Both players open from this book ...");
+    println!("    --book-plies <n> ... for the first n moves of the game (default 12).");
+    println!("  nboard           Runs as an engine for NBoard, speaking its protocol on stdin and stdout.");
+    println!("    --evaluator <name>, --weights <file>");
+    println!("                     As for search.");
+    println!("    --book <file>    Opens from this book, and adds games to it when asked to learn ...");
+    println!("    --book-plies <n> ... for the first n moves of the game (default 12).");
+    println!("  openings         Counts games and results by opening.");
+    println!("    --input <file>   Transcripts of the games (default: stdin).");
+    println!("  import [record]  Reads a game record written any common way, like \"1. f5 2. d6 3. pass\",");
+    println!("                   from the argument or stdin, and prints it as a transcript with what was");
+    println!("                   normalized; --board-size, --layout and rules as for random.");
+    println!("  book build <book file>");
+    println!("                   Builds an opening book and writes it to the file.");
+    println!("    --input <file>   Transcripts of games to build it from, or - for stdin.");
+    println!("    --games <n>      Without --input, builds it from n random games (default 2000).");
+    println!("    --plies <n>      How many moves of each game to keep (default 12).");
+    println!("    --eval <depth>   Search the book's leaves to this depth (default 6) and back up their values.");
+    println!("  book lookup <book file> <transcript>");
+    println!("                   Lists the book moves in the position.");
+    println!("  train <weights file>");
+    println!("                   Fits pattern weights and writes them to the file.");
+    println!("    --input <file>   Lines of `<transcript> [differential]`, or - for stdin.");
+    println!("                     Unlabeled transcripts must be complete games.");
+    println!("    --games <n>      Without --input, trains on n random games (default 2000).");
+    println!("    --epochs <n>     Passes over the data (default 10).");
+    println!("    --rate <r>       Learning rate (default 0.002).");
+    println!("    --lambda <l>     L2 regularization (default 0.0001).");
+    println!("    --anti           Trains for anti-reversi, recording it in the weights file.");
     println!("  help             This screen.");
     println!();
 }
 
+// true if a bare flag like `--kifu` was passed
+fn flag(args: &[String], name: &str) -> bool {
+    args.iter().any(|a| a == name)
+}
+
+// the value following an option like `--highlight C5`
+fn option<'a>(args: &'a [String], name: &str) -> Option<&'a str> {
+    let index = args.iter().position(|a| a == name)?;
+    args.get(index + 1).map(|s| s.as_str())
+}
+
+// pattern weights from `--weights`, which only fit games on the standard board
+fn pattern(args: &[String], game: &Game) -> Pattern {
+    let path = option(args, "--weights").expect("--weights is required for the pattern evaluator");
+    Pattern::check(game.board()).unwrap_or_else(|e| panic!("Can't use the pattern evaluator: {}", e));
+    Pattern::load(path).expect("unable to load pattern weights")
+}
+
+// squares across the board, from `--board-size`
+fn board_size(args: &[String]) -> usize {
+    let size = match option(args, "--board-size") {
+        Some(s) => s.parse().expect("--board-size must be a number"),
+        None => SIZE,
+    };
+    if !size.is_multiple_of(2) || !(MIN_SIZE..=MAX_SIZE).contains(&size) {
+        panic!("--board-size must be even, from {} to {}", MIN_SIZE, MAX_SIZE);
+    }
+    size
+}
+
+// a new game with the board size, layout and rules given on the command line
+fn start(args: &[String]) -> Game {
+    let supply = match option(args, "--discs") {
+        None => Supply::Unlimited,
+        Some(n) => {
+            let n = n.parse().expect("--discs must be a number of discs");
+            if flag(args, "--hand-over") { Supply::HandOver(n) } else { Supply::Pass(n) }
+        }
+    };
+    let rules = Rules {
+        anti: flag(args, "--anti"),
+        placement: flag(args, "--placement"),
+        supply,
+    };
+    let size = board_size(args);
+    let board = match option(args, "--layout") {
+        None if rules.placement => Board::with_size(size),
+        None | Some("standard") => layout::standard(size),
+        Some("cross") => layout::cross(size),
+        Some(random) if random.starts_with("random") => {
+            let blocked = match random.strip_prefix("random:") {
+                Some(n) => n.parse().expect("--layout random:<n> needs a number of blocked squares"),
+                None => 0,
+            };
+            let seed = Seed::from_string(option(args, "--seed").unwrap_or("layout").to_string());
+            layout::random(size, blocked, &mut seed.rng())
+        }
+        Some(rows) => layout::parse(rows).expect("--layout must be standard, cross, random[:<blocked>] or rows like ..../.LD./.DL./...."),
+    };
+    Game::from_layout(board, rules)
+}
+
+#[allow(clippy::needless_borrow)]
 fn run_incremental(game: &Game, collector: Sender<Vec<Transcript>> ) {
     let mut s = Incremental::new(&game);
     loop {
         match s.next() {
             None => return,
             Some(result) => {
-                for g in Transcript::symmetrical(result.transcript) {
+                for g in Transcript::symmetrical(result.transcript, SIZE) {
                     collector.send(g).unwrap();
                 }
             }
@@ -66,8 +246,8 @@ fn run_incremental(game: &Game, collector: Sender<Vec<Transcript>> ) {
 }
 
 fn generate() {
-    let parallel = crate::solvers::parallel::Parallel::new();
-    let mut printer = collector::Printer::new();
+    let parallel = reversi::solvers::parallel::Parallel::new();
+    let mut printer = Printer::new();
 
     let game_printers: Vec<(Game, Sender<Vec<Transcript>>)> = parallel.queue
         .into_iter()
@@ -79,8 +259,8 @@ fn generate() {
 }
 
 fn benchmark() {
-    let parallel = crate::solvers::parallel::Parallel::new();
-    let mut counter = collector::Counter::new();
+    let parallel = reversi::solvers::parallel::Parallel::new();
+    let mut counter = Counter::new();
 
     let game_counters: Vec<(Game, Sender<Vec<Transcript>>)> = parallel.queue
             .into_iter()
@@ -91,8 +271,71 @@ fn benchmark() {
     game_counters.into_par_iter().for_each(move |(g,c)| { run_incremental(&g, c)});
 }
 
-fn random() {
-    let game = Game::new();
+fn available_threads() -> usize {
+    std::thread::available_parallelism().map(|n| n.get()).unwrap_or(1)
+}
+
+// time to reach a fixed depth from a few midgame positions, on one thread and on many
+fn benchmark_search(args: &[String]) {
+    let depth = match option(args, "--depth") {
+        Some(d) => d.parse().expect("--depth must be a number"),
+        None => 8,
+    };
+    let threads = match option(args, "--threads") {
+        Some(t) => t.parse().expect("--threads must be a number"),
+        None => available_threads(),
+    };
+
+    // twenty random moves into a few games
+    let positions: Vec<Game> = (0..3)
+        .map(|i| {
+            let seed = Seed::from_string(format!("benchmark {}", i));
+            let played = Random::new(Game::new(), seed).next().unwrap();
+            Game::from_transcript(played.transcript[..20].to_vec())
+        })
+        .collect();
+
+    println!("Searching {} positions to depth {} ...\n", positions.len(), depth);
+
+    let mut single_total = Duration::from_secs(0);
+    let mut parallel_total = Duration::from_secs(0);
+
+    for game in &positions {
+
+        let mut single = Deepening::new(Heuristic::new());
+        single.max_depth = depth;
+        single.set_table(Arc::new(TranspositionTable::new(64)));
+        let timer = Instant::now();
+        let s = single.search(game, None, |_| ());
+        let single_elapsed = timer.elapsed();
+
+        let mut parallel = Smp::new(Heuristic::new(), threads, 64);
+        parallel.max_depth = depth;
+        let threads = parallel.threads();
+        let timer = Instant::now();
+        let m = parallel.search(game, None, |_| ());
+        let parallel_elapsed = timer.elapsed();
+
+        println!("  {}", Transcript::stringify(&game.transcript));
+        println!("    1 thread:   {:?} (score {:+}, best {})", single_elapsed, s.result.score, Transcript::stringify(&s.result.pv[..1]));
+        println!("    {} threads: {:?} (score {:+}, best {})", threads, parallel_elapsed, m.result.score, Transcript::stringify(&m.result.pv[..1]));
+
+        single_total += single_elapsed;
+        parallel_total += parallel_elapsed;
+    }
+
+    println!();
+    println!(
+        "Speedup with {} threads: {:.2}x ({:?} vs {:?})",
+        threads,
+        single_total.as_secs_f64() / parallel_total.as_secs_f64(),
+        single_total,
+        parallel_total
+    );
+}
+
+fn random(args: &[String]) {
+    let game = start(args);
     let seed = Seed::new();
     eprintln!(
         "Generating random games from seed \"{}\"",
@@ -107,6 +350,558 @@ fn random() {
     }
 }
 
+fn svg(args: &[String]) {
+    let transcript = match args.first() {
+        Some(t) if !t.starts_with("--") => Transcript::from_string(t),
+        _ => Vec::new(),
+    };
+
+    let mut svg = Svg::new();
+    svg.move_numbers = flag(args, "--kifu");
+    if let Some(squares) = option(args, "--highlight") {
+        svg.highlights = Transcript::from_string(squares)
+            .into_iter()
+            .filter_map(|t| match t {
+                Transcript::Play(p) => Some(p),
+                Transcript::Pass => None,
+            })
+            .collect::<Vec<Position>>();
+    }
+
+    print!("{}", svg.render(&start(args).replay(transcript)));
+}
+
+fn gif(args: &[String]) {
+    let transcript = match args.first() {
+        Some(t) if !t.starts_with("--") => Transcript::from_string(t),
+        _ => Transcript::from_string(MANUBU_MARUO),
+    };
+
+    let mut gif = Gif::new();
+    gif.start = start(args);
+    gif.flips = !flag(args, "--no-flips");
+    if let Some(delay) = option(args, "--delay") {
+        gif.delay = delay.parse().expect("--delay must be a number");
+    }
+    if let Some(size) = option(args, "--size") {
+        gif.cell_size = size.parse().expect("--size must be a number");
+    }
+
+    let output = gif.render(&transcript).unwrap_or_else(|e| panic!("Unable to render the GIF: {}", e));
+    std::io::stdout().write_all(&output).expect("unable to write to stdout");
+}
+
+fn export_html(args: &[String]) {
+    let transcript = match args.first() {
+        Some(t) if !t.starts_with("--") => Transcript::from_string(t),
+        _ => Transcript::from_string(MANUBU_MARUO),
+    };
+
+    let mut html = Html::new();
+    html.start = start(args);
+    if let Some(title) = option(args, "--title") {
+        html.title = title.to_string();
+    }
+    if let Some(depth) = option(args, "--eval") {
+        let depth = depth.parse().expect("--eval must be a number");
+        let mut searcher = AlphaBeta::new(Heuristic::new(), depth);
+
+        // scores are reported from dark's point of view after each move
+        html.evaluations = (1..=transcript.len())
+            .map(|ply| {
+                let game = html.start.replay(transcript[..ply].to_vec());
+                let score = if game.is_complete() {
+                    final_score(&game, Disk::Dark)
+                } else {
+                    let score = searcher.search(&game).score;
+                    if game.turn == Disk::Dark { score } else { -score }
+                };
+                Some(score as f64 / DISC as f64)
+            })
+            .collect();
+    }
+
+    print!("{}", html.render(&transcript));
+}
+
+fn search(args: &[String]) {
+    let transcript = match args.first() {
+        Some(t) if !t.starts_with("--") => Transcript::from_string(t),
+        _ => Vec::new(),
+    };
+    let depth = match option(args, "--depth") {
+        Some(d) => d.parse().expect("--depth must be a number"),
+        None => 6,
+    };
+
+    let time = option(args, "--time").map(|t| Duration::from_millis(t.parse().expect("--time must be a number")));
+    let infinite = flag(args, "--infinite");
+    let threads = match option(args, "--threads") {
+        Some(t) => t.parse().expect("--threads must be a number"),
+        None => 1,
+    };
+
+    let game = start(args).replay(transcript);
+    game.pp();
+    println!();
+
+    match option(args, "--evaluator").unwrap_or("heuristic") {
+        "parity" => report_search(Parity, depth, time, threads, infinite, &game),
+        "heuristic" => report_search(Heuristic::new(), depth, time, threads, infinite, &game),
+        "pattern" => report_search(pattern(args, &game), depth, time, threads, infinite, &game),
+        other => panic!("Unknown evaluator {}; must be heuristic, pattern or parity", other),
+    }
+}
+
+fn report_search<E: Evaluator + Clone + Send>(evaluator: E, depth: usize, time: Option<Duration>, threads: usize, infinite: bool, game: &Game) {
+    let timer = Instant::now();
+    let result = if time.is_none() && !infinite {
+        AlphaBeta::new(evaluator, depth).search(game)
+    } else {
+        let mut smp = Smp::new(evaluator, threads, 256);
+        if infinite {
+            let stop = smp.stop_handle();
+            std::thread::spawn(move || {
+                let _ = std::io::stdin().read_line(&mut String::new());
+                stop.store(true, std::sync::atomic::Ordering::Relaxed);
+            });
+        }
+        smp.search(game, time, |iteration| println!("{}", iteration)).result
+    };
+    let elapsed = timer.elapsed();
+
+    match result.best {
+        None => println!("Best move: none, the game is complete"),
+        Some(t) => println!("Best move: {}", t),
+    }
+    println!("Score: {:+.2} for {}", result.score as f64 / DISC as f64, game.turn);
+    println!("PV: {}", Transcript::stringify(&result.pv));
+    println!("Nodes: {} in {:?}", result.nodes, elapsed);
+}
+
+fn analyze(args: &[String]) {
+    let record = match args.first() {
+        Some(t) if !t.starts_with("--") || t == "--" => t.as_str(),
+        _ => MANUBU_MARUO,
+    };
+    let start = start(args);
+    let imported = reversi::import::parse(record, &start).unwrap_or_else(|e| panic!("Unable to read the game: {}", e));
+    for n in &imported.normalizations {
+        eprintln!("Normalized: {}", n);
+    }
+    let game = start.replay(imported.transcript);
+
+    let depth = match option(args, "--depth") {
+        Some(d) => d.parse().expect("--depth must be a number"),
+        None => 6,
+    };
+    match option(args, "--evaluator").unwrap_or("heuristic") {
+        "parity" => report_analysis(Analyzer::new(Parity, depth), &game, args),
+        "heuristic" => report_analysis(Analyzer::new(Heuristic::new(), depth), &game, args),
+        "pattern" => report_analysis(Analyzer::new(pattern(args, &game), depth), &game, args),
+        other => panic!("Unknown evaluator {}; must be heuristic, pattern or parity", other),
+    }
+}
+
+fn report_analysis<E: Evaluator>(mut analyzer: Analyzer<E>, game: &Game, args: &[String]) {
+    if let Some(empties) = option(args, "--exact") {
+        analyzer.exact_empties = empties.parse().expect("--exact must be a number of empty squares");
+    }
+    let analysis = analyzer.analyze(game);
+
+    match option(args, "--format").unwrap_or("text") {
+        "text" => {
+            print!("{}", analysis::report(&analysis));
+            if let Some(result) = game.result() {
+                println!("Result: {}", result);
+            }
+        }
+        "json" => print!("{}", analysis::to_json(game, &analysis)),
+        "ggf" => match to_annotated_ggf(game, &analysis::evaluations(&analysis)) {
+            Ok(ggf) => println!("{}", ggf),
+            Err(e) => panic!("Unable to write GGF: {}", e),
+        },
+        other => panic!("Unknown format {}; must be text, json or ggf", other),
+    }
+}
+
+fn train(args: &[String]) {
+    let output = match args.first() {
+        Some(path) if !path.starts_with("--") => path.clone(),
+        _ => return help(),
+    };
+
+    let rules = Rules { anti: flag(args, "--anti"), ..Rules::new() };
+    let mut trainer = Trainer::with_rules(Pattern::new(), rules);
+    if let Some(rate) = option(args, "--rate") {
+        trainer.rate = rate.parse().expect("--rate must be a number");
+    }
+    if let Some(lambda) = option(args, "--lambda") {
+        trainer.lambda = lambda.parse().expect("--lambda must be a number");
+    }
+    let epochs: usize = match option(args, "--epochs") {
+        Some(e) => e.parse().expect("--epochs must be a number"),
+        None => 10,
+    };
+
+    match option(args, "--input") {
+        Some(path) => {
+            let reader: Box<dyn BufRead> = match path {
+                "-" => Box::new(BufReader::new(std::io::stdin())),
+                _ => Box::new(BufReader::new(File::open(path).expect("unable to open input"))),
+            };
+            for (number, line) in reader.lines().enumerate() {
+                if let Err(e) = trainer.add_line(&line.expect("unable to read input")) {
+                    panic!("line {} of the input: {}", number + 1, e);
+                }
+            }
+        }
+        None => {
+            let count = match option(args, "--games") {
+                Some(g) => g.parse().expect("--games must be a number"),
+                None => 2000,
+            };
+            eprintln!("Playing {} random games ...", count);
+            for game in training_games(count, rules) {
+                trainer.add_game(&game);
+            }
+        }
+    }
+
+    if trainer.is_empty() {
+        eprintln!("No positions to train on!");
+        return;
+    }
+
+    eprintln!("Training on {} positions ...", trainer.len());
+    let mut rng = StdRng::seed_from_u64(0);
+    for epoch in 1..=epochs {
+        let timer = Instant::now();
+        let rmse = trainer.epoch(&mut rng);
+        eprintln!("  epoch {}: rmse {:.3} discs ({:?})", epoch, rmse, timer.elapsed());
+    }
+
+    trainer.pattern.save(&output).expect("unable to write weights");
+    eprintln!("Wrote {} (final rmse {:.3} discs)", output, trainer.rmse());
+}
+
+// Random games played out from each of the parallel solver's seed positions, in parallel.
+fn training_games(count: usize, rules: Rules) -> Vec<Game> {
+    let openings = reversi::solvers::parallel::Parallel::new().queue;
+    (0..count)
+        .into_par_iter()
+        .map(|i| {
+            let opening = Game::with_rules(SIZE, rules).replay(openings[i % openings.len()].transcript.clone());
+            let seed = Seed::from_string(format!("train {}", i));
+            Random::new(opening, seed).next().unwrap()
+        })
+        .collect()
+}
+
+// builds a player from a name like `heuristic` or `pattern:weights.bin`
+fn player(spec: &str) -> Box<dyn Player> {
+    match spec.split_once(':') {
+        Some(("pattern", path)) => Box::new(Engine::new(Pattern::load(path).expect("unable to load pattern weights"))),
+        Some(("engine", command)) => {
+            let words: Vec<&str> = command.split_whitespace().collect();
+            let (program, args) = words.split_first().expect("engine: needs a command to run");
+            Box::new(External::spawn(program, args, 8).expect("unable to start engine"))
+        }
+        _ => match spec {
+            "first" => Box::new(FirstMove),
+            "heuristic" => Box::new(Engine::new(Heuristic::new())),
+            "parity" => Box::new(Engine::new(Parity)),
+            other => panic!("Unknown player {}", other),
+        },
+    }
+}
+
+fn play_match(args: &[String]) {
+    let seconds = |name: &str, default: f64| match option(args, name) {
+        Some(s) => Duration::from_secs_f64(s.parse().expect("times must be numbers")),
+        None => Duration::from_secs_f64(default),
+    };
+    let clock = Clock::new(seconds("--clock", 60.0), seconds("--increment", 0.0));
+
+    let first = option(args, "--dark").unwrap_or("heuristic");
+    let second = option(args, "--light").unwrap_or("heuristic");
+    if first.starts_with("pattern:") || second.starts_with("pattern:") {
+        Pattern::check(start(args).board()).unwrap_or_else(|e| panic!("Can't play a pattern player: {}", e));
+    }
+
+    let book = option(args, "--book").map(|path| Arc::new(Book::load(path).expect("unable to load opening book")));
+    let book_plies = match option(args, "--book-plies") {
+        Some(p) => p.parse().expect("--book-plies must be a number"),
+        None => 12,
+    };
+    let players = |dark: &str, light: &str, round: usize| -> (Box<dyn Player>, Box<dyn Player>) {
+        match &book {
+            Some(book) => {
+                let booked = |spec: &str, disk: Disk| -> Box<dyn Player> {
+                    let seed = Seed::from_string(format!("{} {} {}", disk, round, Seed::new().string));
+                    Box::new(Booked::new(book.clone(), book_plies, player(spec), seed))
+                };
+                (booked(dark, Disk::Dark), booked(light, Disk::Light))
+            }
+            None => (player(dark), player(light)),
+        }
+    };
+
+    let suite = if let Some(path) = option(args, "--suite") {
+        Some(Suite::load(path).expect("unable to load opening suite"))
+    } else if let Some(count) = option(args, "--random-openings") {
+        let count = count.parse().expect("--random-openings must be a number");
+        let plies = match option(args, "--plies") {
+            Some(p) => p.parse().expect("--plies must be a number"),
+            None => 8,
+        };
+        let window: f64 = match option(args, "--window") {
+            Some(w) => w.parse().expect("--window must be a number"),
+            None => 2.0,
+        };
+        let mut searcher = AlphaBeta::new(Heuristic::new(), 4);
+        let window = (window * DISC as f64) as i32;
+        Some(Suite::balanced(count, plies, &mut searcher, window, Seed::new()))
+    } else {
+        None
+    };
+
+    let suite = match suite {
+        Some(suite) => {
+            assert!(
+                option(args, "--layout").is_none() && option(args, "--board-size").is_none(),
+                "opening suites are played on the standard board, so can't be played with --layout or --board-size"
+            );
+            suite
+        }
+        None => {
+            let (dark, light) = players(first, second, 0);
+            let mut contest = Contest::new(start(args), dark, light);
+            contest.set_clock(clock);
+            contest.play();
+
+            contest.game.pp();
+            if let (Some(loser), Some(reason)) = (contest.forfeit, &contest.forfeit_reason) {
+                println!("{} forfeits: {}", loser, reason);
+            }
+            for (disk, clock) in &[(Disk::Dark, contest.dark_clock), (Disk::Light, contest.light_clock)] {
+                if let Some(c) = clock {
+                    println!("{} clock: {:?} remaining", disk, c.remaining);
+                }
+            }
+            return;
+        }
+    };
+
+    // every opening is played twice, with each player taking each side once; the
+    // results are wins, draws and losses for the first player
+    let mut results = [0; 3];
+    let rules = start(args).rules();
+    assert!(!rules.placement, "opening suites start from the centre discs, so can't be played with --placement");
+    println!("Playing {} openings with each side ...\n", suite.openings.len());
+    for (round, opening) in suite.openings.iter().enumerate() {
+        for &first_side in &[Disk::Dark, Disk::Light] {
+            let (dark, light) = match first_side {
+                Disk::Dark => players(first, second, round),
+                Disk::Light => players(second, first, round),
+            };
+            let opening = Game::with_rules(SIZE, rules).replay(opening.transcript.clone());
+            let mut contest = Contest::new(opening.clone(), dark, light);
+            contest.set_clock(clock);
+            contest.play();
+
+            let outcome = contest.outcome(first_side).expect("contests are played to the end or forfeited");
+            results[(1 - outcome) as usize] += 1;
+
+            let result = match (contest.forfeit, &contest.forfeit_reason, contest.result()) {
+                (Some(loser), Some(reason), _) => format!("{} forfeits: {}", loser, reason),
+                (_, _, Some(result)) => result.to_string(),
+                _ => String::new(),
+            };
+            println!(
+                "  {} {} as {}: {}",
+                Transcript::stringify(&opening.transcript),
+                first,
+                first_side,
+                result
+            );
+        }
+    }
+
+    println!();
+    println!("{} vs {}: {} wins, {} draws, {} losses", first, second, results[0], results[1], results[2]);
+}
+
+fn nboard(args: &[String]) {
+    match option(args, "--evaluator").unwrap_or("heuristic") {
+        "parity" => run_nboard(NBoard::new(Parity), args),
+        "heuristic" => run_nboard(NBoard::new(Heuristic::new()), args),
+        "pattern" => {
+            let path = option(args, "--weights").expect("--weights is required for the pattern evaluator");
+            let pattern = Pattern::load(path).expect("unable to load pattern weights");
+            run_nboard(NBoard::new(pattern), args)
+        }
+        other => panic!("Unknown evaluator {}; must be heuristic, pattern or parity", other),
+    }
+}
+
+fn run_nboard<E: Evaluator + Clone>(mut engine: NBoard<E>, args: &[String]) {
+    if let Some(path) = option(args, "--book") {
+        // a book that doesn't exist yet is started by learning
+        engine.book = Some(Book::load(path).unwrap_or_else(|_| Book::new()));
+        engine.book_path = Some(path.into());
+    }
+    if let Some(plies) = option(args, "--book-plies") {
+        engine.book_plies = plies.parse().expect("--book-plies must be a number");
+    }
+
+    let stdin = std::io::stdin();
+    engine
+        .run(stdin.lock(), std::io::stdout())
+        .expect("unable to talk to the front end");
+}
+
+fn opening_report(args: &[String]) {
+    let reader: Box<dyn BufRead> = match option(args, "--input") {
+        None | Some("-") => Box::new(BufReader::new(std::io::stdin())),
+        Some(path) => Box::new(BufReader::new(File::open(path).expect("unable to open input"))),
+    };
+
+    let mut openings: HashMap<&str, Record> = HashMap::new();
+    for line in reader.lines() {
+        let line = line.expect("unable to read input");
+        if line.trim().is_empty() {
+            continue;
+        }
+        let game = Game::from_transcript(Transcript::from_string(line.trim()));
+        openings.entry(game.opening_name().unwrap_or("(unnamed)")).or_default().add(game.result());
+    }
+
+    let mut rows: Vec<(&str, Record)> = openings.into_iter().collect();
+    rows.sort_by(|a, b| b.1.games.cmp(&a.1.games).then(a.0.cmp(b.0)));
+
+    println!("{:<20} {:>7} {:>7} {:>7} {:>7}", "Opening", "Games", "Dark", "Draws", "Light");
+    for (name, r) in rows {
+        println!("{:<20} {:>7} {:>7} {:>7} {:>7}", name, r.games, r.dark_wins, r.draws, r.light_wins);
+    }
+}
+
+fn import(args: &[String]) {
+    let record = match args.first() {
+        Some(record) if !record.starts_with("--") || record == "--" => record.clone(),
+        _ => {
+            let mut record = String::new();
+            std::io::stdin().read_to_string(&mut record).expect("unable to read the record");
+            record
+        }
+    };
+
+    let imported = reversi::import::parse(&record, &start(args)).unwrap_or_else(|e| panic!("Unable to read the record: {}", e));
+    println!("{}", Transcript::stringify(&imported.transcript));
+    for n in imported.normalizations {
+        eprintln!("Normalized: {}", n);
+    }
+}
+
+fn build_book(args: &[String]) {
+    let output = match args.first() {
+        Some(path) if !path.starts_with("--") => path.clone(),
+        _ => return help(),
+    };
+    let plies = match option(args, "--plies") {
+        Some(p) => p.parse().expect("--plies must be a number"),
+        None => 12,
+    };
+    let depth = match option(args, "--eval") {
+        Some(d) => d.parse().expect("--eval must be a number"),
+        None => 6,
+    };
+
+    let mut book = Book::new();
+    match option(args, "--input") {
+        Some(path) => {
+            let reader: Box<dyn BufRead> = match path {
+                "-" => Box::new(BufReader::new(std::io::stdin())),
+                _ => Box::new(BufReader::new(File::open(path).expect("unable to open input"))),
+            };
+            for line in reader.lines() {
+                let line = line.expect("unable to read input");
+                if !line.trim().is_empty() {
+                    book.add_game(&Game::from_transcript(Transcript::from_string(line.trim())), plies);
+                }
+            }
+        }
+        None => {
+            let count = match option(args, "--games") {
+                Some(g) => g.parse().expect("--games must be a number"),
+                None => 2000,
+            };
+            eprintln!("Playing {} random games ...", count);
+            let games: Vec<Game> = (0..count)
+                .into_par_iter()
+                .map(|i| Random::new(Game::new(), Seed::from_string(format!("book {}", i))).next().unwrap())
+                .collect();
+            for game in games {
+                book.add_game(&game, plies);
+            }
+        }
+    }
+
+    if book.is_empty() {
+        eprintln!("No games to build a book from!");
+        return;
+    }
+
+    eprintln!("Evaluating the leaves of {} positions to depth {} ...", book.len(), depth);
+    let timer = Instant::now();
+    let leaves = book.evaluate(&mut AlphaBeta::new(Heuristic::new(), depth));
+    book.propagate();
+    eprintln!("  {} leaves in {:?}", leaves, timer.elapsed());
+
+    book.save(&output).expect("unable to write opening book");
+    eprintln!("Wrote {} ({} positions)", output, book.len());
+}
+
+fn lookup_book(args: &[String]) {
+    let path = match args.first() {
+        Some(path) => path,
+        None => return help(),
+    };
+    let transcript = match args.get(1) {
+        Some(t) => Transcript::from_string(t),
+        None => Vec::new(),
+    };
+
+    let book = Book::load(path).expect("unable to load opening book");
+    let game = Game::from_transcript(transcript);
+    game.pp();
+    println!();
+
+    let value = |v: Option<i32>| match v {
+        Some(v) => format!("{:+.2}", v as f64 / DISC as f64),
+        None => "?".to_string(),
+    };
+
+    match book.get(&game) {
+        None => return println!("Out of book"),
+        Some(node) => println!("Book value {} for {} after {} games", value(node.value), game.turn, node.games()),
+    }
+
+    for c in book.lookup(&game) {
+        println!(
+            "  {}  weight {:.3}  value {:>6}  score {:>5.1}% of {} games (+{} ={} -{})",
+            c.play,
+            c.weight,
+            value(c.node.value),
+            c.node.score() * 100.0,
+            c.node.games(),
+            c.node.wins,
+            c.node.draws,
+            c.node.losses
+        );
+    }
+}
+
 fn demos() {
     println!("\nDemos!");
 
@@ -147,7 +942,7 @@ fn demos() {
 
     println!("\nAnd symmetrical transcripts (three more identical solutions) ...");
     timer = Instant::now();
-    let symmetrical = Transcript::symmetrical(game.transcript);
+    let symmetrical = Transcript::symmetrical(game.transcript, SIZE);
     elapsed = timer.elapsed();
     println!("Elapsed: {:?}\n", elapsed);
 
@@ -196,7 +991,7 @@ fn demos() {
     println!("Playing through a basic contest (first moves) ...\n ");
 
     println!("Result:");
-    let mut contest = Contest::new(Game::new(), Player::new(), Player::new());
+    let mut contest = Contest::new(Game::new(), Box::new(FirstMove), Box::new(FirstMove));
 
     timer = Instant::now();
     contest.play();
@@ -0,0 +1,473 @@
+use std::io::{self, BufRead, Write};
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Instant;
+
+use rand::rngs::StdRng;
+
+use crate::board::{Board, MAX_SIZE, MIN_SIZE, SIZE};
+use crate::book::Book;
+use crate::disk::Disk;
+use crate::eval::{Evaluator, DISC};
+use crate::game::Game;
+use crate::position::Position;
+use crate::rules::Rules;
+use crate::solvers::alphabeta::AlphaBeta;
+use crate::solvers::deepening::Deepening;
+use crate::solvers::random::Seed;
+use crate::solvers::table::TranspositionTable;
+use crate::transcript::Transcript;
+
+// Starting positions in GGF are the size, each row from the top with - for empty
+// squares, * for dark, O for light and # for blocked, and the side to move. Games
+// can start from any layout on a board of any size we play, with dark to move.
+fn ggf_board(board: &Board) -> String {
+    let squares: String = board
+        .rows()
+        .concat()
+        .chars()
+        .map(|c| match c {
+            'D' => '*',
+            'L' => 'O',
+            '.' => '-',
+            other => other,
+        })
+        .collect();
+    format!("{} {} *", board.size(), squares)
+}
+
+fn parse_ggf_board(value: &str) -> Result<Board, String> {
+    let words: Vec<&str> = value.split_whitespace().collect();
+    let (size, squares) = match words.as_slice() {
+        [size, squares, "*"] => (size.parse().unwrap_or(0), *squares),
+        [_, _, "O"] => return Err("only games with dark to move first are supported".to_string()),
+        _ => return Err(format!("invalid GGF board {}", value)),
+    };
+    if !(MIN_SIZE..=MAX_SIZE).contains(&size) || squares.chars().count() != size * size {
+        return Err(format!("only square boards from {0}x{0} to {1}x{1} are supported", MIN_SIZE, MAX_SIZE));
+    }
+
+    let rows: Vec<String> = squares
+        .chars()
+        .map(|c| match c {
+            '*' => 'D',
+            'O' => 'L',
+            '-' => '.',
+            other => other,
+        })
+        .collect::<Vec<char>>()
+        .chunks(size)
+        .map(|row| row.iter().collect())
+        .collect();
+    Board::from_rows(&rows).ok_or(format!("invalid GGF board {}", value))
+}
+
+// An engine speaking the NBoard protocol, one command per line.
+pub struct NBoard<E: Evaluator + Clone> {
+    pub name: String,
+    pub depth: usize,
+    // opened from for the first `book_plies` moves, and extended by `learn`
+    pub book: Option<Book>,
+    pub book_path: Option<PathBuf>,
+    pub book_plies: usize,
+    game: Game,
+    evaluator: E,
+    deepening: Deepening<E>,
+    table: Arc<TranspositionTable>,
+    rng: StdRng,
+}
+
+impl<E: Evaluator + Clone> NBoard<E> {
+    pub fn new(evaluator: E) -> Self {
+        let table = Arc::new(TranspositionTable::new(64));
+        let mut deepening = Deepening::new(evaluator.clone());
+        deepening.set_table(table.clone());
+
+        Self {
+            name: "Reversi".to_string(),
+            depth: 8,
+            book: None,
+            book_path: None,
+            book_plies: 12,
+            game: Game::new(),
+            evaluator,
+            deepening,
+            table,
+            rng: Seed::new().rng(),
+        }
+    }
+
+    // Answers commands until the input ends or the front end says `quit`.
+    pub fn run<R: BufRead, W: Write>(&mut self, input: R, mut output: W) -> io::Result<()> {
+        for line in input.lines() {
+            let line = line?;
+            if line.trim() == "quit" {
+                break;
+            }
+            for response in self.handle(&line) {
+                writeln!(output, "{}", response)?;
+            }
+            output.flush()?;
+        }
+        Ok(())
+    }
+
+    // The responses to a single command. Commands we don't know are ignored, as the
+    // protocol asks, and ones we can't carry out are reported with `status`.
+    pub fn handle(&mut self, line: &str) -> Vec<String> {
+        let line = line.trim();
+        let (command, rest) = line.split_once(' ').unwrap_or((line, ""));
+        let rest = rest.trim();
+
+        match command {
+            "nboard" => vec![format!("set myname {}", self.name)],
+            "set" => {
+                let (key, value) = rest.split_once(' ').unwrap_or((rest, ""));
+                match key {
+                    "depth" => match value.trim().parse() {
+                        Ok(depth) if depth > 0 => {
+                            self.depth = depth;
+                            Vec::new()
+                        }
+                        _ => vec![format!("status invalid depth {}", value)],
+                    },
+                    // books and pattern weights are for the standard board
+                    "game" => match parse_ggf(value) {
+                        Ok(game) if game.board().size() != SIZE => vec!["status only games on the standard board are supported".to_string()],
+                        Ok(game) => {
+                            self.game = game;
+                            Vec::new()
+                        }
+                        Err(message) => vec![format!("status {}", message)],
+                    },
+                    _ => Vec::new(),
+                }
+            }
+            "move" => {
+                let mv = rest.split('/').next().unwrap_or("");
+                match parse_move(mv).and_then(|t| self.game.apply(t).ok()) {
+                    Some(game) => {
+                        self.game = game;
+                        Vec::new()
+                    }
+                    None => vec![format!("status invalid move {}", rest)],
+                }
+            }
+            "go" => vec![self.go()],
+            "hint" => self.hint(rest.parse().unwrap_or(1)),
+            "ping" => vec![format!("pong {}", rest)],
+            "learn" => self.learn(),
+            _ => Vec::new(),
+        }
+    }
+
+    // `=== <move>/<eval>/<seconds>`, with the evaluation in discs for the side to move.
+    fn go(&mut self) -> String {
+        let timer = Instant::now();
+
+        if let (Some(book), true) = (&self.book, self.game.transcript.len() < self.book_plies) {
+            if let Some(t) = book.choose(&self.game, &mut self.rng) {
+                return format!("=== {}", format_move(t));
+            }
+        }
+
+        self.deepening.max_depth = self.depth;
+        let iteration = self.deepening.search(&self.game, None, |_| ());
+        let best = iteration.result.best.unwrap_or(Transcript::Pass);
+
+        format!(
+            "=== {}/{:.2}/{:.1}",
+            format_move(best),
+            iteration.result.score as f64 / DISC as f64,
+            timer.elapsed().as_secs_f64()
+        )
+    }
+
+    // `search <pv> <eval> 0 <depth>` for each of the `count` best moves, best first.
+    fn hint(&mut self, count: usize) -> Vec<String> {
+        if self.game.is_complete() {
+            return vec!["status the game is over".to_string()];
+        }
+        let moves = self.game.valid_moves();
+        if moves.is_empty() {
+            return vec![format!("search PA 0.00 0 {}", self.depth)];
+        }
+
+        let mut searcher = AlphaBeta::new(self.evaluator.clone(), self.depth.saturating_sub(1));
+        searcher.table = Some(self.table.clone());
+
+        let mut lines: Vec<(i32, String)> = moves
+            .into_iter()
+            .map(|vm| {
+                let first = Transcript::Play(vm.position);
+                let result = searcher.search(&self.game.play(vm));
+                let pv: Vec<String> = std::iter::once(first).chain(result.pv).map(format_move).collect();
+                (-result.score, pv.join(""))
+            })
+            .collect();
+        lines.sort_by_key(|(score, _)| std::cmp::Reverse(*score));
+
+        lines
+            .into_iter()
+            .take(count.max(1))
+            .map(|(score, pv)| format!("search {} {:.2} 0 {}", pv, score as f64 / DISC as f64, self.depth))
+            .collect()
+    }
+
+    // Adds the current game to the book, and saves it if it came from a file.
+    fn learn(&mut self) -> Vec<String> {
+        if let Some(book) = self.book.as_mut() {
+            book.add_game(&self.game, self.book_plies);
+            if let Some(path) = &self.book_path {
+                if let Err(e) = book.save(path) {
+                    return vec![format!("status unable to save book: {}", e), "learned".to_string()];
+                }
+            }
+        }
+        vec!["learned".to_string()]
+    }
+}
+
+fn format_move(t: Transcript) -> String {
+    match t {
+        Transcript::Pass => "PA".to_string(),
+        Transcript::Play(_) => t.to_string(),
+    }
+}
+
+// A move like `F5` or `f5`, `J10` on the larger boards, or `PA` for a pass.
+pub fn parse_move(s: &str) -> Option<Transcript> {
+    let s = s.trim().to_ascii_uppercase();
+    if s == "PA" || s == "PASS" {
+        return Some(Transcript::Pass);
+    }
+
+    let mut chars = s.chars();
+    let x = chars.next()?;
+    let row = chars.as_str();
+    if !row.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    let y: usize = row.parse().ok()?;
+    if !('A'..='P').contains(&x) || !(1..=MAX_SIZE).contains(&y) {
+        return None;
+    }
+
+    Some(Transcript::Play(Position {
+        x: x as usize - 'A' as usize,
+        y: y - 1,
+    }))
+}
+
+// Reads a game in GGF, like `(;GM[Othello]PB[me]PW[you]BO[8 ... *]B[F5]W[F6//1.2];)`.
+pub fn parse_ggf(text: &str) -> Result<Game, String> {
+    let mut game = Game::new();
+    let mut rules = Rules::new();
+    let mut rest = text.trim();
+
+    while let Some(open) = rest.find('[') {
+        let close = rest[open..].find(']').ok_or("unterminated GGF tag")? + open;
+        let tag = rest[..open].trim_start_matches(|c: char| !c.is_ascii_uppercase()).trim();
+        let value = &rest[open + 1..close];
+        rest = &rest[close + 1..];
+
+        let disk = match tag {
+            "BO" => {
+                if !game.transcript.is_empty() {
+                    return Err("GGF board after the moves".to_string());
+                }
+                game = Game::from_layout(parse_ggf_board(value)?, Rules::new());
+                continue;
+            }
+            // the game type is the board size followed by variant letters, with `a` for anti
+            "TY" => {
+                rules.anti = value.trim_start_matches(|c: char| c.is_ascii_digit()).contains('a');
+                continue;
+            }
+            "B" => Disk::Dark,
+            "W" => Disk::Light,
+            _ => continue,
+        };
+
+        let mv = value.split('/').next().unwrap_or("");
+        if disk != game.turn {
+            return Err(format!("{} moved out of turn in GGF", disk));
+        }
+        game = parse_move(mv)
+            .and_then(|t| game.apply(t).ok())
+            .ok_or(format!("invalid move {} in GGF", value))?;
+    }
+
+    Ok(Game::from_layout(game.layout(), rules).replay(game.transcript))
+}
+
+// Writes the game as GGF, for `set game`.
+pub fn to_ggf(game: &Game) -> Result<String, String> {
+    write_ggf(game, "?", &[])
+}
+
+// Writes the game as GGF with an evaluation of each move, in discs for the player
+// who made it, like `B[F5/-2.00]`; and its result, if it's over.
+pub fn to_annotated_ggf(game: &Game, evals: &[Option<f64>]) -> Result<String, String> {
+    let result = match game.result() {
+        Some(result) => format!("{:+}.000", result.margin(Disk::Dark)),
+        None => String::from("?"),
+    };
+    write_ggf(game, &result, evals)
+}
+
+// The game type is the board size, with `a` for anti-reversi; GGF has nothing for
+// placed centres or limited supplies of discs.
+fn write_ggf(game: &Game, result: &str, evals: &[Option<f64>]) -> Result<String, String> {
+    let rules = game.rules();
+    if rules.placement || rules.supply.discs().is_some() {
+        return Err(format!("GGF can't record games under these rules: {}", rules));
+    }
+    let variant = if rules.anti { "a" } else { "" };
+    let size = game.board().size();
+    let mut ggf = format!("(;GM[Othello]PC[Reversi]PB[Dark]PW[Light]RE[{}]TY[{}{}]BO[{}]", result, size, variant, ggf_board(&game.layout()));
+    let mut turn = Disk::Dark;
+    for (ply, t) in game.transcript.iter().enumerate() {
+        let tag = match turn {
+            Disk::Dark => "B",
+            Disk::Light => "W",
+        };
+        match evals.get(ply).copied().flatten() {
+            Some(eval) => ggf.push_str(&format!("{}[{}/{:.2}]", tag, format_move(*t), eval)),
+            None => ggf.push_str(&format!("{}[{}]", tag, format_move(*t))),
+        }
+        turn = turn.opposite();
+    }
+    ggf.push_str(";)");
+    Ok(ggf)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::eval::heuristic::Heuristic;
+    use crate::position::PositionState;
+
+    const GGF: &str = "(;GM[Othello]PC[NBoard]DT[2024-01-01]PB[me]PW[you]RE[?]TI[5:00]TY[8]BO[8 ---------------------------O*------*O--------------------------- *]B[F5//0.01]W[D6]B[C3/0.5/1.0];)";
+
+    #[test]
+    fn reads_ggf() {
+        let game = parse_ggf(GGF).unwrap();
+        assert_eq!(Transcript::stringify(&game.transcript), "F5D6C3");
+        assert_eq!(game.turn, Disk::Light);
+
+        assert!(parse_ggf("(;GM[Othello]BO[8 ---------------------------O*------*O--------------------------- O];)").is_err());
+        assert!(parse_ggf("(;GM[Othello]BO[6 --------------O*----*O-------------- *]B[F5];)").is_err());
+        assert!(parse_ggf("(;GM[Othello]BO[3 ---------O *];)").is_err());
+        assert!(parse_ggf("(;GM[Othello]BO[8 ---------------------------O*------*O--------------------------- *]W[F5];)").is_err());
+        assert!(parse_ggf("(;GM[Othello]B[A1];)").is_err());
+    }
+
+    #[test]
+    fn writes_ggf() {
+        let game = Game::from_transcript(Transcript::from_string("F5D6C3"));
+        let ggf = to_ggf(&game).unwrap();
+        assert!(ggf.ends_with("B[F5]W[D6]B[C3];)"));
+        assert_eq!(parse_ggf(&ggf).unwrap().transcript, game.transcript);
+
+        // anti-reversi is game type 8a
+        assert!(parse_ggf(&ggf).unwrap().rules().is_standard());
+        let anti = Game::with_rules(8, Rules::anti()).replay(game.transcript);
+        let ggf = to_ggf(&anti).unwrap();
+        assert!(ggf.contains("TY[8a]"));
+        assert_eq!(parse_ggf(&ggf).unwrap().rules(), Rules::anti());
+
+        // other layouts are written out in full
+        let mut board = crate::layout::cross(8);
+        board.set(7, 7, PositionState::Blocked);
+        let game = Game::from_layout(board, Rules::new()).replay(Transcript::from_string("E3"));
+        let ggf = to_ggf(&game).unwrap();
+        assert!(ggf.contains("BO[8 ---------------------------OO------**"));
+        assert!(ggf.contains("-# *]"));
+        let read = parse_ggf(&ggf).unwrap();
+        assert_eq!(read.layout(), board);
+        assert_eq!(read.board(), game.board());
+
+        // annotations go after each move, and finished games get their result
+        let game = Game::from_transcript(Transcript::from_string(crate::transcript::MANUBU_MARUO));
+        let ggf = to_annotated_ggf(&game, &[Some(-1.5), None]).unwrap();
+        assert!(ggf.contains("RE[+64.000]"));
+        assert!(ggf.contains("B[E6/-1.50]W[F4]B[E3]"));
+        assert_eq!(parse_ggf(&ggf).unwrap().transcript, game.transcript);
+
+        // the game type has the board's size, and rules GGF can't express aren't written
+        let small = Game::with_size(6).replay(Transcript::from_string("C2"));
+        let ggf = to_ggf(&small).unwrap();
+        assert!(ggf.contains("TY[6]BO[6 "));
+        assert_eq!(parse_ggf(&ggf).unwrap(), small);
+
+        // and moves on the larger boards take two digits
+        let large = Game::with_size(16).replay(Transcript::from_string("I10"));
+        assert_eq!(parse_ggf(&to_ggf(&large).unwrap()).unwrap(), large);
+        assert_eq!(parse_move("p16"), Some(Transcript::Play(Position { x: 15, y: 15 })));
+        assert_eq!(parse_move("Q1"), None);
+        assert_eq!(parse_move("A17"), None);
+        assert!(to_ggf(&Game::with_rules(8, Rules::historical())).is_err());
+    }
+
+    #[test]
+    fn scripted_session() {
+        let mut engine = NBoard::new(Heuristic::new());
+        let script = format!("nboard 2\nset depth 3\nset game {}\nmove d3\nping 1\ngo\nhint 2\nmove A1\nlearn\nquit\nping 2\n", GGF);
+
+        let mut output = Vec::new();
+        engine.run(io::Cursor::new(script), &mut output).unwrap();
+        let output = String::from_utf8(output).unwrap();
+        let lines: Vec<&str> = output.lines().collect();
+
+        assert_eq!(lines[0], "set myname Reversi");
+        assert_eq!(lines[1], "pong 1");
+        assert_eq!(engine.depth, 3);
+
+        // the engine's move is legal for dark after F5D6C3D3
+        assert!(lines[2].starts_with("=== "));
+        let mv = lines[2][4..].split('/').next().unwrap();
+        let game = Game::from_transcript(Transcript::from_string("F5D6C3D3"));
+        assert!(game.apply(parse_move(mv).unwrap()).is_ok());
+
+        assert!(lines[3].starts_with("search "));
+        assert!(lines[4].starts_with("search "));
+        assert!(lines[3].ends_with(" 0 3"));
+        assert_eq!(lines[5], "status invalid move A1");
+        assert_eq!(lines[6], "learned");
+        assert_eq!(lines.len(), 7);
+    }
+
+    #[test]
+    fn hints_are_sorted() {
+        let mut engine = NBoard::new(Heuristic::new());
+        engine.depth = 2;
+        let hints = engine.handle("hint 4");
+        let scores: Vec<f64> = hints.iter().map(|h| h.split(' ').nth(2).unwrap().parse().unwrap()).collect();
+
+        assert_eq!(scores.len(), 4);
+        assert!(scores.windows(2).all(|w| w[0] >= w[1]));
+
+        // a finished game has no move to suggest
+        engine.game = Game::from_transcript(Transcript::from_string(crate::transcript::MANUBU_MARUO));
+        assert_eq!(engine.handle("hint 1"), vec!["status the game is over"]);
+    }
+
+    #[test]
+    fn learns_into_the_book() {
+        let mut engine = NBoard::new(Heuristic::new());
+        engine.book = Some(Book::new());
+        engine.book_plies = 4;
+        engine.handle(&format!("set game {}", GGF));
+        assert_eq!(engine.handle("learn"), vec!["learned"]);
+
+        // the book only covers the standard board
+        let small = to_ggf(&Game::with_size(6)).unwrap();
+        assert_eq!(engine.handle(&format!("set game {}", small)), vec!["status only games on the standard board are supported"]);
+        assert_eq!(engine.book.as_ref().unwrap().len(), 4);
+
+        // and then plays from it
+        engine.handle("set game (;GM[Othello]BO[8 ---------------------------O*------*O--------------------------- *]B[F5]W[D6];)");
+        assert_eq!(engine.handle("go"), vec!["=== C3"]);
+    }
+}
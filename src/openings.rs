@@ -0,0 +1,77 @@
+use crate::board::SIZE;
+use crate::position::Position;
+use crate::transcript::Transcript;
+
+// Named openings, all written starting with F5. Longer lines refine the shorter ones
+// they start with.
+pub const OPENINGS: &[(&str, &str)] = &[
+    ("Perpendicular", "F5D6"),
+    ("Diagonal", "F5F6"),
+    ("Parallel", "F5F4"),
+    ("Tiger", "F5D6C3D3C4"),
+    ("Leader's Tiger", "F5D6C3D3C4F4F6"),
+    ("Stephenson", "F5D6C3D3C4F4C5B3C2"),
+    ("Cow", "F5D6C5F4E3"),
+    ("Chimney", "F5D6C5F4E3F6"),
+    ("Rose", "F5D6C5F4E3C6D3F6E6D7"),
+    ("Rabbit", "F5F6E6F4E3"),
+    ("Buffalo", "F5F6E6F4C3"),
+    ("Heath", "F5F6E6F4G5"),
+];
+
+// The name of the longest opening the transcript starts with, in any orientation.
+// Only meaningful for games on the standard board.
+pub fn name(transcript: &[Transcript]) -> Option<&'static str> {
+    let mut best: Option<(usize, &'static str)> = None;
+
+    for f in Position::SYMMETRIES.iter() {
+        let oriented: Vec<Transcript> = transcript
+            .iter()
+            .map(|t| match t {
+                Transcript::Play(p) => Transcript::Play(f(*p, SIZE)),
+                Transcript::Pass => Transcript::Pass,
+            })
+            .collect();
+
+        for (name, line) in OPENINGS {
+            let line = Transcript::from_string(line);
+            let longer = best.is_none_or(|(length, _)| line.len() > length);
+            if longer && oriented.starts_with(&line) {
+                best = Some((line.len(), name));
+            }
+        }
+    }
+
+    best.map(|(_, name)| name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::Game;
+
+    #[test]
+    fn openings_are_legal() {
+        for (_, line) in OPENINGS {
+            let game = Game::from_transcript(Transcript::from_string(line));
+            assert!(!game.is_complete());
+        }
+    }
+
+    #[test]
+    fn longest_match() {
+        assert_eq!(name(&Transcript::from_string("F5D6C3D3C4")), Some("Tiger"));
+        assert_eq!(name(&Transcript::from_string("F5D6C3D3C4F4F6F3")), Some("Leader's Tiger"));
+        assert_eq!(name(&Transcript::from_string("F5D6C3")), Some("Perpendicular"));
+        assert_eq!(name(&Transcript::from_string("F5")), None);
+        assert_eq!(name(&[]), None);
+    }
+
+    #[test]
+    fn any_orientation() {
+        let rose = Transcript::from_string("F5D6C5F4E3C6D3F6E6D7G3");
+        for t in Transcript::symmetrical(rose, SIZE) {
+            assert_eq!(name(&t), Some("Rose"));
+        }
+    }
+}
@@ -1,18 +1,22 @@
-use crate::board::{MAX_X, MAX_Y};
+use crate::board::SIZE;
 use crate::direction::Direction;
 use crate::disk::Disk;
 
 #[derive(Copy, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum PositionState {
     Empty,
     Dark,
     Light,
+    // a hole in the board: never played on, and it breaks every line through it
+    Blocked,
 }
 
 impl PositionState {
     pub fn opposite(self) -> Self {
         match self {
             PositionState::Empty => PositionState::Empty,
+            PositionState::Blocked => PositionState::Blocked,
             PositionState::Dark => PositionState::Light,
             PositionState::Light => PositionState::Dark,
         }
@@ -35,14 +39,15 @@ pub struct Position {
 }
 
 impl Position {
-    pub fn neighbor(&self, d: &Direction) -> Option<Self> {
+    // the next square over in direction `d`, on a board `size` squares across
+    pub fn neighbor(&self, d: &Direction, size: usize) -> Option<Self> {
         match d {
             Direction::North => self.north(),
-            Direction::NorthEast => self.north_east(),
-            Direction::East => self.east(),
-            Direction::SouthEast => self.south_east(),
-            Direction::South => self.south(),
-            Direction::SouthWest => self.south_west(),
+            Direction::NorthEast => self.north_east(size),
+            Direction::East => self.east(size),
+            Direction::SouthEast => self.south_east(size),
+            Direction::South => self.south(size),
+            Direction::SouthWest => self.south_west(size),
             Direction::West => self.west(),
             Direction::NorthWest => self.north_west(),
         }
@@ -59,16 +64,16 @@ impl Position {
         }
     }
 
-    fn north_east(&self) -> Option<Self> {
-        self.north()?.east()
+    fn north_east(&self, size: usize) -> Option<Self> {
+        self.north()?.east(size)
     }
 
     fn north_west(&self) -> Option<Self> {
         self.north()?.west()
     }
 
-    fn south(&self) -> Option<Self> {
-        if self.y == MAX_Y {
+    fn south(&self, size: usize) -> Option<Self> {
+        if self.y + 1 == size {
             None
         } else {
             Some(Position {
@@ -78,12 +83,12 @@ impl Position {
         }
     }
 
-    fn south_east(&self) -> Option<Self> {
-        self.south()?.east()
+    fn south_east(&self, size: usize) -> Option<Self> {
+        self.south(size)?.east(size)
     }
 
-    fn east(&self) -> Option<Self> {
-        if self.x == MAX_X {
+    fn east(&self, size: usize) -> Option<Self> {
+        if self.x + 1 == size {
             None
         } else {
             Some(Position {
@@ -104,13 +109,13 @@ impl Position {
         }
     }
 
-    fn south_west(&self) -> Option<Self> {
-        self.south()?.west()
+    fn south_west(&self, size: usize) -> Option<Self> {
+        self.south(size)?.west()
     }
 
-    // Transformations
+    // Transformations, on a board `size` squares across
 
-    pub fn rotate(mut position: Position) -> Position {
+    pub fn rotate(mut position: Position, _size: usize) -> Position {
         let tmp_x = position.y;
         let tmp_y = position.x;
         position.x = tmp_x;
@@ -119,42 +124,72 @@ impl Position {
         position
     }
 
-    pub fn flip(mut position: Position) -> Position {
-        position.x = MAX_X - position.x;
-        position.y = MAX_Y - position.y;
+    pub fn flip(mut position: Position, size: usize) -> Position {
+        position.x = size - 1 - position.x;
+        position.y = size - 1 - position.y;
 
         position
     }
+
+    pub fn mirror(mut position: Position, size: usize) -> Position {
+        position.x = size - 1 - position.x;
+
+        position
+    }
+
+    // All eight symmetries of the board, starting with the identity. Only the first
+    // four preserve the starting position; the rest mirror it.
+    pub const SYMMETRIES: [fn(Position, usize) -> Position; 8] = [
+        |p, _| p,
+        Position::rotate,
+        Position::flip,
+        |p, s| Position::flip(Position::rotate(p, s), s),
+        Position::mirror,
+        |p, s| Position::mirror(Position::rotate(p, s), s),
+        |p, s| Position::mirror(Position::flip(p, s), s),
+        |p, s| Position::mirror(Position::flip(Position::rotate(p, s), s), s),
+    ];
 }
 
 #[derive(Debug)]
 pub struct PositionIter {
-    index: usize,
+    next: Position,
+    size: usize,
 }
 
 impl PositionIter {
     pub fn new() -> Self {
-        PositionIter { index: 0 }
+        PositionIter::with_size(SIZE)
+    }
+
+    pub fn with_size(size: usize) -> Self {
+        PositionIter { next: Position { x: 0, y: 0 }, size }
+    }
+}
+
+impl Default for PositionIter {
+    fn default() -> Self {
+        Self::new()
     }
 }
 
 impl Iterator for PositionIter {
     type Item = Position;
     fn next(&mut self) -> Option<Self::Item> {
-        // check to see if our index is out of bounds.
-        if self.index >= (MAX_X + 1) * (MAX_Y + 1) {
+        // check to see if we've run off the bottom of the board.
+        if self.next.y >= self.size {
             return None;
         }
 
-        // convert index into X and Y coordinates.
-        let x = self.index % (MAX_X + 1);
-        let y = self.index / (MAX_Y + 1);
+        let position = self.next;
 
-        // build the response.
-        let position = Position { x, y };
-
-        // increment the index
-        self.index += 1;
+        // move along the row, wrapping around to the start of the next one; kept
+        // as coordinates, since dividing by a size that isn't constant is slow
+        self.next.x += 1;
+        if self.next.x == self.size {
+            self.next.x = 0;
+            self.next.y += 1;
+        }
 
         // result!
         Some(position)
@@ -175,6 +210,29 @@ mod tests {
         assert_eq!(p.nth(63), Some(Position { x: 7, y: 7 }));
         assert_eq!(p.next(), None);
         assert_eq!(p.nth(200), None);
+
+        p = PositionIter::with_size(6);
+        assert_eq!(p.nth(7), Some(Position { x: 1, y: 1 }));
+        assert_eq!(p.last(), Some(Position { x: 5, y: 5 }));
+    }
+
+    #[test]
+    fn neighbors() {
+        let corner = Position { x: 5, y: 5 };
+        assert_eq!(corner.neighbor(&Direction::East, 6), None);
+        assert_eq!(corner.neighbor(&Direction::East, 8), Some(Position { x: 6, y: 5 }));
+        assert_eq!(corner.neighbor(&Direction::NorthWest, 6), Some(Position { x: 4, y: 4 }));
+    }
+
+    #[test]
+    fn symmetries() {
+        let corner = Position { x: 0, y: 1 };
+        let mut images: Vec<Position> = Position::SYMMETRIES.iter().map(|f| f(corner, 8)).collect();
+        images.sort();
+        images.dedup();
+        assert_eq!(images.len(), 8);
+        assert!(Position::SYMMETRIES.iter().all(|f| f(Position { x: 9, y: 9 }, 10) != Position { x: 0, y: 0 } || f(Position { x: 0, y: 0 }, 10) == Position { x: 9, y: 9 }));
+        assert_eq!(Position::flip(Position { x: 0, y: 1 }, 10), Position { x: 9, y: 8 });
     }
 
 }
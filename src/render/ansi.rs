@@ -0,0 +1,212 @@
+// Terminal rendering of a game, with optional colors and markers.
+
+use std::io::IsTerminal;
+
+use crate::game::Game;
+use crate::position::{Position, PositionState};
+use crate::render::move_numbers;
+
+const RESET: &str = "\x1b[0m";
+const BOARD: &str = "\x1b[42m"; // green felt
+const LAST_MOVE: &str = "\x1b[43m"; // yellow background
+const FLIPPED: &str = "\x1b[102m"; // bright green background
+const DARK: &str = "\x1b[30m";
+const LIGHT: &str = "\x1b[97m";
+const LEGAL: &str = "\x1b[33m";
+const BLOCKED: &str = "\x1b[90m";
+
+#[derive(Clone, Copy, Debug)]
+pub struct Ansi {
+    pub color: bool,
+    pub legal_moves: bool,
+    pub last_move: bool,
+    pub move_numbers: bool,
+}
+
+impl Ansi {
+    // colors and markers on; suitable for a terminal.
+    pub fn new() -> Self {
+        Ansi {
+            color: true,
+            legal_moves: true,
+            last_move: true,
+            move_numbers: false,
+        }
+    }
+
+    // same as `new`, but falls back to plain characters when stdout isn't a TTY.
+    pub fn for_stdout() -> Self {
+        Ansi {
+            color: std::io::stdout().is_terminal(),
+            ..Ansi::new()
+        }
+    }
+
+    pub fn render(&self, game: &Game) -> String {
+        let board = game.board();
+        // move numbers run to three digits on boards larger than 9x9
+        let width = if self.move_numbers { board.squares().to_string().len() + 1 } else { 2 };
+
+        let legal: Vec<Position> = if self.legal_moves && !game.is_complete() {
+            game.move_iter().map(|vm| vm.position).collect()
+        } else {
+            Vec::new()
+        };
+
+        let (placed, flipped) = match game.last_move() {
+            Some(vm) if self.last_move => (Some(vm.position), vm.affected),
+            _ => (None, Vec::new()),
+        };
+
+        let numbers = move_numbers(game);
+
+        // row numbers take two columns on boards larger than 9x9
+        let size = board.size();
+        let label = size.to_string().len();
+        let mut output = " ".repeat(label);
+        for x in 0..size {
+            output.push_str(&format!("{:>width$}", (b'a' + x as u8) as char, width = width));
+        }
+
+        for y in 0..size {
+            output.push_str(&format!("\n{:>label$}", y + 1, label = label));
+            for x in 0..size {
+                let position = Position { x, y };
+                let state = board.get(x, y);
+
+                let marker = if placed == Some(position) {
+                    Marker::LastMove
+                } else if flipped.contains(&position) {
+                    Marker::Flipped
+                } else if legal.contains(&position) {
+                    Marker::Legal
+                } else {
+                    Marker::None
+                };
+
+                let number = if self.move_numbers { numbers.get(&position).map(|(n, _)| *n) } else { None };
+
+                output.push_str(&self.cell(state, marker, number, width));
+            }
+            if self.color {
+                output.push_str(RESET);
+            }
+        }
+        output.push('\n');
+        output
+    }
+
+    fn cell(&self, state: PositionState, marker: Marker, number: Option<usize>, width: usize) -> String {
+        let glyph = match (number, state, marker) {
+            (Some(n), _, _) => n.to_string(),
+            (None, PositionState::Empty, Marker::Legal) => String::from(if self.color { "·" } else { "*" }),
+            (None, PositionState::Empty, _) => String::from("•"),
+            (None, PositionState::Dark, _) => String::from(if self.color { "●" } else { "D" }),
+            (None, PositionState::Light, _) => String::from(if self.color { "●" } else { "L" }),
+            (None, PositionState::Blocked, _) => String::from(if self.color { "■" } else { "#" }),
+        };
+
+        if !self.color {
+            // without colors, the last move and its flips are flagged by the padding character.
+            let pad = match marker {
+                Marker::LastMove => '>',
+                Marker::Flipped => '+',
+                _ => ' ',
+            };
+            let padding = width.saturating_sub(glyph.chars().count());
+            return format!("{}{}", pad.to_string().repeat(padding), glyph);
+        }
+
+        let background = match marker {
+            Marker::LastMove => LAST_MOVE,
+            Marker::Flipped => FLIPPED,
+            _ => BOARD,
+        };
+
+        let foreground = match (state, marker) {
+            (PositionState::Dark, _) => DARK,
+            (PositionState::Light, _) => LIGHT,
+            (PositionState::Blocked, _) => BLOCKED,
+            (PositionState::Empty, Marker::Legal) => LEGAL,
+            (PositionState::Empty, _) => BOARD,
+        };
+
+        format!("{}{}{:>width$}", background, foreground, glyph, width = width)
+    }
+}
+
+impl Default for Ansi {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Marker {
+    None,
+    Legal,
+    LastMove,
+    Flipped,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transcript::{Transcript, MANUBU_MARUO};
+    use crate::solvers::random::{Random, Seed};
+
+    fn plain() -> Ansi {
+        Ansi {
+            color: false,
+            legal_moves: false,
+            last_move: false,
+            move_numbers: false,
+        }
+    }
+
+    #[test]
+    fn plain_matches_board_display() {
+        let g = Game::from_transcript(Transcript::from_string(MANUBU_MARUO));
+        assert_eq!(plain().render(&g), g.board().to_string());
+    }
+
+    #[test]
+    fn markers() {
+        let mut ansi = plain();
+        ansi.legal_moves = true;
+        ansi.last_move = true;
+
+        // opening position has four legal moves for dark, and no last move.
+        let g = Game::new();
+        assert_eq!(ansi.render(&g).matches('*').count(), 4);
+        assert_eq!(ansi.render(&g).matches('>').count(), 0);
+
+        // after D3 dark flips D4, and light has three replies.
+        let g = Game::from_transcript(Transcript::from_string("D3"));
+        let output = ansi.render(&g);
+        assert_eq!(output.matches(">D").count(), 1);
+        assert_eq!(output.matches("+D").count(), 1);
+        assert_eq!(output.matches('*').count(), 3);
+    }
+
+    #[test]
+    fn numbers() {
+        let mut ansi = plain();
+        ansi.move_numbers = true;
+
+        let g = Game::from_transcript(Transcript::from_string(MANUBU_MARUO));
+        let output = ansi.render(&g);
+        for n in 1..=9 {
+            assert!(output.contains(&format!("  {}", n)));
+        }
+        assert!(!output.contains("10"));
+
+        // every number on a full 16x16 board stands apart from its neighbours
+        let g = Random::new(Game::with_size(16), Seed::from_string("ansi".to_string())).next().unwrap();
+        let output = ansi.render(&g);
+        let numbers: Vec<usize> = output.lines().skip(1).flat_map(|row| row.split_whitespace().skip(1)).filter_map(|cell| cell.parse().ok()).collect();
+        assert_eq!(numbers.len(), move_numbers(&g).len());
+        assert!(numbers.len() > 99);
+        assert!(numbers.iter().all(|&n| n <= numbers.len()));
+    }
+}
@@ -0,0 +1,404 @@
+// Animated GIF replays of a game, with a small self-contained encoder.
+
+use std::collections::HashMap;
+use std::convert::TryFrom;
+
+use crate::board::Board;
+use crate::game::Game;
+use crate::position::{Position, PositionState};
+use crate::transcript::Transcript;
+
+// palette indices
+const BOARD: u8 = 0;
+const GRID: u8 = 1;
+const DARK: u8 = 2;
+const LIGHT: u8 = 3;
+const LAST_MOVE: u8 = 4;
+const FLIPPED: u8 = 5;
+const BLOCKED: u8 = 6;
+
+const PALETTE: [[u8; 3]; 8] = [
+    [0x2e, 0x7d, 0x32], // board
+    [0x0b, 0x3d, 0x0e], // grid
+    [0x11, 0x11, 0x11], // dark
+    [0xf5, 0xf5, 0xf5], // light
+    [0xfd, 0xd8, 0x35], // last move
+    [0x81, 0xc7, 0x84], // flipped
+    [0x55, 0x55, 0x55], // blocked
+    [0x00, 0x00, 0x00],
+];
+
+// the palette has 8 entries, so pixels are 3 bit codes.
+const MIN_CODE_SIZE: u8 = 3;
+const MAX_CODE: u16 = 4095;
+
+#[derive(Clone, Debug)]
+pub struct Gif {
+    pub cell_size: usize,
+    // frame delay, in hundredths of a second
+    pub delay: u16,
+    // show an extra frame with each new disc placed before its flips are applied
+    pub flips: bool,
+    // the position the transcript is played from
+    pub start: Game,
+}
+
+impl Gif {
+    pub fn new() -> Self {
+        Gif {
+            cell_size: 32,
+            delay: 60,
+            flips: true,
+            start: Game::new(),
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.cell_size * self.start.board().size() + 1
+    }
+
+    pub fn height(&self) -> usize {
+        self.cell_size * self.start.board().size() + 1
+    }
+
+    // The replay as a GIF file, unless it's too big for one: GIF dimensions are 16 bit.
+    pub fn render(&self, transcript: &[Transcript]) -> Result<Vec<u8>, String> {
+        let too_big = |_| format!("a {}x{} image is too big for a GIF", self.width(), self.height());
+        let width = u16::try_from(self.width()).map_err(too_big)?;
+        let height = u16::try_from(self.height()).map_err(too_big)?;
+
+        let frames = self.frames(transcript);
+        let mut output = Vec::new();
+
+        // header and logical screen descriptor, with an 8 color global palette
+        output.extend_from_slice(b"GIF89a");
+        output.extend_from_slice(&width.to_le_bytes());
+        output.extend_from_slice(&height.to_le_bytes());
+        output.push(0b1111_0010); // global color table, 8 bit color resolution, 2^(2+1) entries
+        output.push(BOARD);
+        output.push(0);
+        for color in &PALETTE {
+            output.extend_from_slice(color);
+        }
+
+        // loop forever
+        output.extend_from_slice(&[0x21, 0xff, 0x0b]);
+        output.extend_from_slice(b"NETSCAPE2.0");
+        output.extend_from_slice(&[0x03, 0x01, 0x00, 0x00, 0x00]);
+
+        let last = frames.len().saturating_sub(1);
+        for (index, frame) in frames.iter().enumerate() {
+            // hold the final position a little longer before looping
+            let delay = if index == last { self.delay.saturating_mul(4) } else { self.delay };
+
+            // graphic control extension
+            output.extend_from_slice(&[0x21, 0xf9, 0x04, 0x00]);
+            output.extend_from_slice(&delay.to_le_bytes());
+            output.extend_from_slice(&[0x00, 0x00]);
+
+            // image descriptor covering the whole screen, no local palette
+            output.push(0x2c);
+            output.extend_from_slice(&[0, 0, 0, 0]);
+            output.extend_from_slice(&width.to_le_bytes());
+            output.extend_from_slice(&height.to_le_bytes());
+            output.push(0);
+
+            output.push(MIN_CODE_SIZE);
+            for block in lzw_encode(frame).chunks(255) {
+                output.push(block.len() as u8);
+                output.extend_from_slice(block);
+            }
+            output.push(0);
+        }
+
+        output.push(0x3b);
+        Ok(output)
+    }
+
+    // Palette-indexed pixels for every frame of the replay.
+    pub fn frames(&self, transcript: &[Transcript]) -> Vec<Vec<u8>> {
+        let mut frames = vec![self.draw(self.start.board(), None, &[])];
+
+        for ply in 1..=transcript.len() {
+            let game = self.start.replay(transcript[..ply].to_vec());
+            match game.last_move() {
+                None => frames.push(self.draw(game.board(), None, &[])),
+                Some(vm) => {
+                    if self.flips {
+                        let mut placed = *game.board();
+                        for p in &vm.affected {
+                            placed.flip(p.x, p.y);
+                        }
+                        frames.push(self.draw(&placed, Some(vm.position), &vm.affected));
+                    }
+                    frames.push(self.draw(game.board(), Some(vm.position), &[]));
+                }
+            }
+        }
+
+        frames
+    }
+
+    fn draw(&self, board: &Board, last_move: Option<Position>, flipped: &[Position]) -> Vec<u8> {
+        let (width, height, cell) = (self.width(), self.height(), self.cell_size);
+        let mut pixels = vec![BOARD; width * height];
+
+        for p in board.iter() {
+            let (left, top) = (p.x * cell, p.y * cell);
+
+            let background = if board.get(p.x, p.y) == PositionState::Blocked {
+                BLOCKED
+            } else if last_move == Some(p) {
+                LAST_MOVE
+            } else if flipped.contains(&p) {
+                FLIPPED
+            } else {
+                BOARD
+            };
+
+            let disc = match board.get(p.x, p.y) {
+                PositionState::Empty | PositionState::Blocked => None,
+                PositionState::Dark => Some(DARK),
+                PositionState::Light => Some(LIGHT),
+            };
+
+            let center = cell as f64 / 2.0;
+            let radius = cell as f64 * 0.42;
+
+            for dy in 0..cell {
+                for dx in 0..cell {
+                    let distance = ((dx as f64 + 0.5 - center).powi(2) + (dy as f64 + 0.5 - center).powi(2)).sqrt();
+                    let color = match disc {
+                        // a thin dark rim keeps light discs visible on pale highlights
+                        Some(_) if distance <= radius && distance > radius - 1.0 => DARK,
+                        Some(c) if distance <= radius => c,
+                        _ => background,
+                    };
+                    pixels[(top + dy) * width + left + dx] = color;
+                }
+            }
+        }
+
+        // grid lines, including the outer border
+        for i in 0..=board.size() {
+            for y in 0..height {
+                pixels[y * width + i * cell] = GRID;
+            }
+        }
+        for i in 0..=board.size() {
+            for x in 0..width {
+                pixels[i * cell * width + x] = GRID;
+            }
+        }
+
+        pixels
+    }
+}
+
+impl Default for Gif {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// Variable width LZW as used by GIF, packed least significant bit first.
+fn lzw_encode(pixels: &[u8]) -> Vec<u8> {
+    let clear: u16 = 1 << MIN_CODE_SIZE;
+    let end = clear + 1;
+
+    let mut writer = BitWriter::new();
+    let mut table: HashMap<(u16, u8), u16> = HashMap::new();
+    let mut next_code = end + 1;
+    let mut code_size = MIN_CODE_SIZE + 1;
+
+    writer.write(clear, code_size);
+
+    let mut pixels = pixels.iter();
+    let mut current = match pixels.next() {
+        Some(p) => *p as u16,
+        None => {
+            writer.write(end, code_size);
+            return writer.finish();
+        }
+    };
+
+    for &pixel in pixels {
+        if let Some(&code) = table.get(&(current, pixel)) {
+            current = code;
+            continue;
+        }
+
+        writer.write(current, code_size);
+
+        if next_code > MAX_CODE {
+            // table is full; start over
+            writer.write(clear, code_size);
+            table.clear();
+            next_code = end + 1;
+            code_size = MIN_CODE_SIZE + 1;
+        } else {
+            table.insert((current, pixel), next_code);
+            if next_code == (1 << code_size) && code_size < 12 {
+                code_size += 1;
+            }
+            next_code += 1;
+        }
+
+        current = pixel as u16;
+    }
+
+    writer.write(current, code_size);
+
+    // decoders add one last table entry after reading the final code, which may widen the end code.
+    if next_code == (1 << code_size) && code_size < 12 {
+        code_size += 1;
+    }
+
+    writer.write(end, code_size);
+    writer.finish()
+}
+
+struct BitWriter {
+    bytes: Vec<u8>,
+    buffer: u32,
+    bits: u8,
+}
+
+impl BitWriter {
+    fn new() -> Self {
+        BitWriter {
+            bytes: Vec::new(),
+            buffer: 0,
+            bits: 0,
+        }
+    }
+
+    fn write(&mut self, code: u16, size: u8) {
+        self.buffer |= (code as u32) << self.bits;
+        self.bits += size;
+        while self.bits >= 8 {
+            self.bytes.push(self.buffer as u8);
+            self.buffer >>= 8;
+            self.bits -= 8;
+        }
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        if self.bits > 0 {
+            self.bytes.push(self.buffer as u8);
+        }
+        self.bytes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transcript::MANUBU_MARUO;
+
+    // minimal GIF LZW decoder, to check the encoder round trips
+    fn lzw_decode(data: &[u8]) -> Vec<u8> {
+        let clear: usize = 1 << MIN_CODE_SIZE;
+        let end = clear + 1;
+        let mut output = Vec::new();
+        let mut table: Vec<Vec<u8>> = Vec::new();
+        let mut code_size = MIN_CODE_SIZE + 1;
+        let mut previous: Option<usize> = None;
+        let (mut buffer, mut bits, mut bytes) = (0u32, 0u8, data.iter());
+
+        let reset = |table: &mut Vec<Vec<u8>>| {
+            table.clear();
+            for i in 0..=end {
+                table.push(vec![i as u8]);
+            }
+        };
+        reset(&mut table);
+
+        loop {
+            while bits < code_size {
+                buffer |= (*bytes.next().unwrap() as u32) << bits;
+                bits += 8;
+            }
+            let code = (buffer & ((1 << code_size) - 1)) as usize;
+            buffer >>= code_size;
+            bits -= code_size;
+
+            if code == clear {
+                reset(&mut table);
+                code_size = MIN_CODE_SIZE + 1;
+                previous = None;
+                continue;
+            }
+            if code == end {
+                return output;
+            }
+
+            let entry = match (table.get(code), previous) {
+                (Some(e), _) => e.clone(),
+                (None, Some(prev)) => {
+                    let mut e = table[prev].clone();
+                    e.push(table[prev][0]);
+                    e
+                }
+                (None, None) => panic!("invalid code {}", code),
+            };
+            output.extend_from_slice(&entry);
+
+            if let Some(prev) = previous {
+                if table.len() <= MAX_CODE as usize {
+                    let mut e = table[prev].clone();
+                    e.push(entry[0]);
+                    table.push(e);
+                    if table.len() == (1 << code_size) && code_size < 12 {
+                        code_size += 1;
+                    }
+                }
+            }
+            previous = Some(code);
+        }
+    }
+
+    #[test]
+    fn lzw_round_trip() {
+        let gif = Gif::new();
+        for frame in gif.frames(&Transcript::from_string(MANUBU_MARUO)) {
+            assert_eq!(lzw_decode(&lzw_encode(&frame)), frame);
+        }
+
+        // long noisy input, to exercise table resets
+        let noise: Vec<u8> = (0..100_000u32).map(|i| ((i * 7919) % 13 % 8) as u8).collect();
+        assert_eq!(lzw_decode(&lzw_encode(&noise)), noise);
+
+        // every length up to a few code size changes, to catch off-by-one widths at the end
+        for length in 0..2_000 {
+            let input = &noise[..length];
+            assert_eq!(lzw_decode(&lzw_encode(input)), input);
+        }
+    }
+
+    #[test]
+    fn frame_count() {
+        let transcript = Transcript::from_string(MANUBU_MARUO);
+        let mut gif = Gif::new();
+
+        // starting position, then a placement and a flip frame per move
+        assert_eq!(gif.frames(&transcript).len(), 1 + 9 * 2);
+
+        gif.flips = false;
+        assert_eq!(gif.frames(&transcript).len(), 1 + 9);
+        assert_eq!(gif.frames(&Transcript::from_string("F5PP")).len(), 3);
+    }
+
+    #[test]
+    fn container() {
+        let gif = Gif::new();
+        let output = gif.render(&Transcript::from_string("F5")).unwrap();
+        assert!(output.starts_with(b"GIF89a"));
+        assert_eq!(output.last(), Some(&0x3b));
+        assert_eq!(u16::from_le_bytes([output[6], output[7]]) as usize, gif.width());
+
+        // wider than 65535 pixels doesn't fit
+        let huge = Gif { cell_size: 8192, ..Gif::new() };
+        assert!(huge.render(&[]).is_err());
+    }
+}
@@ -0,0 +1,277 @@
+// A single-file HTML viewer for stepping through a game.
+
+use crate::game::Game;
+use crate::position::PositionState;
+use crate::transcript::Transcript;
+
+const TEMPLATE: &str = r##"<!DOCTYPE html>
+<html>
+<head>
+<meta charset="utf-8">
+<title>{{title}}</title>
+<style>
+body { font-family: sans-serif; margin: 2em; color: #222; }
+main { display: flex; gap: 2em; align-items: flex-start; }
+#board { display: grid; grid-template-columns: repeat({{columns}}, 44px); gap: 2px; background: #0b3d0e; padding: 2px; }
+.square { width: 44px; height: 44px; background: #2e7d32; display: flex; align-items: center; justify-content: center; }
+.square.last { background: #c0a92e; }
+.square.flipped { background: #81c784; }
+.square.blocked { background: #555; }
+.disc { width: 36px; height: 36px; border-radius: 50%; border: 1px solid #111; }
+.disc.dark { background: #111; }
+.disc.light { background: #f5f5f5; }
+#controls { margin: 1em 0; }
+#moves { border-collapse: collapse; }
+#moves td, #moves th { padding: 2px 8px; text-align: right; }
+#moves tr.current { background: #fdd835; }
+#moves tbody tr { cursor: pointer; }
+</style>
+</head>
+<body>
+<h1>{{title}}</h1>
+<main>
+<div>
+<div id="board"></div>
+<div id="controls">
+<button id="first">&#x23ee;</button>
+<button id="back">&#x25c0;</button>
+<button id="forward">&#x25b6;</button>
+<button id="last">&#x23ed;</button>
+</div>
+<div id="status"></div>
+</div>
+<table id="moves">
+<thead><tr><th>#</th><th>Move</th><th>Dark</th><th>Light</th>{{evaluation_header}}</tr></thead>
+<tbody></tbody>
+</table>
+</main>
+<script>
+const states = {{states}};
+let current = 0;
+
+const board = document.getElementById("board");
+const rows = document.querySelector("#moves tbody");
+
+states.forEach((state, index) => {
+  if (index === 0) return;
+  const row = document.createElement("tr");
+  row.innerHTML = "<td>" + index + "</td><td>" + state.move + "</td><td>" + state.dark + "</td><td>" + state.light + "</td>" +
+    (state.evaluation === undefined ? "" : "<td>" + (state.evaluation === null ? "" : state.evaluation) + "</td>");
+  row.addEventListener("click", () => show(index));
+  rows.appendChild(row);
+});
+
+function show(index) {
+  current = Math.max(0, Math.min(states.length - 1, index));
+  const state = states[current];
+  board.innerHTML = "";
+  for (let i = 0; i < state.board.length; i++) {
+    const square = document.createElement("div");
+    square.className = "square";
+    if (state.placed === i) square.classList.add("last");
+    if (state.flipped.includes(i)) square.classList.add("flipped");
+    const cell = state.board[i];
+    if (cell === "#") square.classList.add("blocked");
+    if (cell === "D" || cell === "L") {
+      const disc = document.createElement("div");
+      disc.className = "disc " + (cell === "D" ? "dark" : "light");
+      square.appendChild(disc);
+    }
+    board.appendChild(square);
+  }
+  Array.from(rows.children).forEach((row, i) => row.classList.toggle("current", i + 1 === current));
+  document.getElementById("status").textContent =
+    "Move " + current + " of " + (states.length - 1) + " - Dark " + state.dark + ", Light " + state.light + " - " + state.next;
+}
+
+document.getElementById("first").onclick = () => show(0);
+document.getElementById("back").onclick = () => show(current - 1);
+document.getElementById("forward").onclick = () => show(current + 1);
+document.getElementById("last").onclick = () => show(states.length - 1);
+document.addEventListener("keydown", (e) => {
+  if (e.key === "ArrowLeft") show(current - 1);
+  if (e.key === "ArrowRight") show(current + 1);
+  if (e.key === "Home") show(0);
+  if (e.key === "End") show(states.length - 1);
+});
+
+show(0);
+</script>
+</body>
+</html>
+"##;
+
+#[derive(Clone, Debug)]
+pub struct Html {
+    pub title: String,
+    // optional engine evaluation after each move, from dark's point of view
+    pub evaluations: Vec<Option<f64>>,
+    // the position the transcript is played from
+    pub start: Game,
+}
+
+impl Html {
+    pub fn new() -> Self {
+        Html {
+            title: String::from("Reversi"),
+            evaluations: Vec::new(),
+            start: Game::new(),
+        }
+    }
+
+    pub fn render(&self, transcript: &[Transcript]) -> String {
+        let states: Vec<String> = (0..=transcript.len())
+            .map(|ply| self.state(&self.start.replay(transcript[..ply].to_vec()), ply))
+            .collect();
+
+        let evaluation_header = if self.evaluations.is_empty() { "" } else { "<th>Eval</th>" };
+
+        fill(
+            TEMPLATE,
+            &[
+                ("title", &escape(&self.title)),
+                ("columns", &self.start.board().size().to_string()),
+                ("evaluation_header", evaluation_header),
+                ("states", &format!("[\n{}\n]", states.join(",\n"))),
+            ],
+        )
+    }
+
+    // one replayed position, as a JSON object for the viewer script.
+    fn state(&self, game: &Game, ply: usize) -> String {
+        let board = game.board();
+        let mut cells = String::new();
+        for y in 0..board.size() {
+            for x in 0..board.size() {
+                cells.push(match board.get(x, y) {
+                    PositionState::Empty => '.',
+                    PositionState::Dark => 'D',
+                    PositionState::Light => 'L',
+                    PositionState::Blocked => '#',
+                });
+            }
+        }
+
+        let index = |x: usize, y: usize| y * board.size() + x;
+        let (placed, flipped) = match game.last_move() {
+            Some(vm) => (
+                index(vm.position.x, vm.position.y).to_string(),
+                vm.affected.iter().map(|p| index(p.x, p.y).to_string()).collect::<Vec<_>>(),
+            ),
+            None => (String::from("null"), Vec::new()),
+        };
+
+        let last = match game.transcript.last() {
+            Some(t) => t.to_string(),
+            None => String::new(),
+        };
+
+        let next = if game.is_complete() {
+            String::from("Complete")
+        } else {
+            format!("{} to move", game.turn)
+        };
+
+        let (dark, light) = game.score();
+
+        let evaluation = if self.evaluations.is_empty() {
+            String::new()
+        } else {
+            // JSON has no NaN or infinity
+            match ply.checked_sub(1).and_then(|i| self.evaluations.get(i)).copied().flatten() {
+                Some(e) if e.is_finite() => format!(", \"evaluation\": {}", e),
+                _ => String::from(", \"evaluation\": null"),
+            }
+        };
+
+        format!(
+            "{{\"board\": \"{}\", \"move\": \"{}\", \"placed\": {}, \"flipped\": [{}], \"dark\": {}, \"light\": {}, \"next\": \"{}\"{}}}",
+            cells,
+            last,
+            placed,
+            flipped.join(", "),
+            dark,
+            light,
+            next,
+            evaluation
+        )
+    }
+}
+
+impl Default for Html {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// Fills in the template's placeholders in a single pass, so nothing filled in is
+// taken for a placeholder itself.
+fn fill(template: &str, values: &[(&str, &str)]) -> String {
+    let mut output = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(open) = rest.find("{{") {
+        output.push_str(&rest[..open]);
+        let after = &rest[open + 2..];
+        let value = after
+            .find("}}")
+            .and_then(|close| values.iter().find(|(name, _)| *name == &after[..close]).map(|(_, value)| (close, value)));
+        match value {
+            Some((close, value)) => {
+                output.push_str(value);
+                rest = &after[close + 2..];
+            }
+            None => {
+                output.push_str("{{");
+                rest = after;
+            }
+        }
+    }
+    output.push_str(rest);
+    output
+}
+
+fn escape(s: &str) -> String {
+    s.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transcript::MANUBU_MARUO;
+
+    #[test]
+    fn replay_states() {
+        let html = Html::new().render(&Transcript::from_string(MANUBU_MARUO));
+        assert!(html.starts_with("<!DOCTYPE html>"));
+        assert!(!html.contains("{{"));
+
+        // the starting position plus one state per move
+        assert_eq!(html.matches("\"board\": ").count(), 10);
+        assert!(html.contains("\"move\": \"C5\""));
+        assert!(html.contains("\"dark\": 13, \"light\": 0, \"next\": \"Complete\""));
+        assert!(!html.contains("\"evaluation\""));
+    }
+
+    #[test]
+    fn evaluations() {
+        let mut html = Html::new();
+        html.title = String::from("<Maruo>");
+        html.evaluations = vec![Some(1.5), None];
+
+        let output = html.render(&Transcript::from_string("F5D6"));
+        assert!(output.contains("<title>&lt;Maruo&gt;</title>"));
+        assert!(output.contains("<th>Eval</th>"));
+        assert_eq!(output.matches("\"evaluation\": null").count(), 2);
+        assert_eq!(output.matches("\"evaluation\": 1.5").count(), 1);
+
+        html.evaluations = vec![Some(f64::NAN), Some(f64::INFINITY)];
+        let output = html.render(&Transcript::from_string("F5D6"));
+        assert_eq!(output.matches("\"evaluation\": null").count(), 3);
+
+        // placeholders in the title are left as they are
+        html.title = String::from("{{columns}} by {{states}}");
+        let output = html.render(&[]);
+        assert!(output.contains("<title>{{columns}} by {{states}}</title>"));
+        assert!(output.contains("repeat(8, 44px)"));
+    }
+}
@@ -0,0 +1,28 @@
+pub mod ansi;
+pub mod gif;
+pub mod html;
+pub mod svg;
+
+use std::collections::HashMap;
+
+use crate::disk::Disk;
+use crate::game::Game;
+use crate::position::Position;
+use crate::transcript::Transcript;
+
+// the move number (1-based, passes are not counted) at which each square was
+// played and who played it, for overlaying a kifu-style move order onto the board.
+pub fn move_numbers(game: &Game) -> HashMap<Position, (usize, Disk)> {
+    let mut numbers = HashMap::new();
+    let mut turn = Disk::Dark;
+
+    for t in &game.transcript {
+        if let Transcript::Play(p) = t {
+            let number = numbers.len() + 1;
+            numbers.insert(*p, (number, turn));
+        }
+        turn = turn.opposite();
+    }
+
+    numbers
+}
@@ -0,0 +1,222 @@
+// Vector diagrams of a game, for printed material and reports.
+
+use crate::disk::Disk;
+use crate::game::Game;
+use crate::position::{Position, PositionState};
+use crate::render::move_numbers;
+
+const STYLE: &str = "\
+.board{fill:#2e7d32}\
+.grid{stroke:#0b3d0e;stroke-width:1.5}\
+.star{fill:#0b3d0e}\
+.highlight{fill:#fdd835;fill-opacity:0.55}\
+.disc{stroke:#111;stroke-width:1}\
+.dark{fill:#111}\
+.light{fill:#f5f5f5}\
+.blocked{fill:#555}\
+.coordinate{fill:#333;font-family:sans-serif;text-anchor:middle;dominant-baseline:central}\
+.number{font-family:sans-serif;font-weight:bold;text-anchor:middle;dominant-baseline:central}\
+.on-dark{fill:#f5f5f5}\
+.on-light{fill:#111}";
+
+#[derive(Clone, Debug)]
+pub struct Svg {
+    pub cell_size: usize,
+    pub coordinates: bool,
+    pub move_numbers: bool,
+    pub highlights: Vec<Position>,
+}
+
+impl Svg {
+    pub fn new() -> Self {
+        Svg {
+            cell_size: 48,
+            coordinates: true,
+            move_numbers: false,
+            highlights: Vec::new(),
+        }
+    }
+
+    // Renders the current position. With `move_numbers` set the figure is a kifu of
+    // the whole transcript instead: every played square shows the disc of the player
+    // who played it, labelled with its move number, over the starting position.
+    pub fn render(&self, game: &Game) -> String {
+        let cell = self.cell_size as f64;
+        let margin = if self.coordinates { cell / 2.0 } else { 0.0 };
+        let size = game.board().size();
+        let board_width = cell * size as f64;
+        let board_height = cell * size as f64;
+        let width = board_width + margin * 2.0;
+        let height = board_height + margin * 2.0;
+
+        let mut output = format!(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{w}\" height=\"{h}\" viewBox=\"0 0 {w} {h}\">\n",
+            w = width,
+            h = height
+        );
+        output.push_str(&format!("<style>{}</style>\n", STYLE));
+        output.push_str(&format!(
+            "<rect class=\"board\" x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\"/>\n",
+            margin, margin, board_width, board_height
+        ));
+
+        for p in &self.highlights {
+            output.push_str(&format!(
+                "<rect class=\"highlight\" x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\"/>\n",
+                margin + cell * p.x as f64,
+                margin + cell * p.y as f64,
+                cell,
+                cell
+            ));
+        }
+
+        // grid lines, including the outer border
+        for i in 0..=size {
+            let offset = margin + cell * i as f64;
+            output.push_str(&format!(
+                "<line class=\"grid\" x1=\"{o}\" y1=\"{}\" x2=\"{o}\" y2=\"{}\"/>\n",
+                margin,
+                margin + board_height,
+                o = offset
+            ));
+        }
+        for i in 0..=size {
+            let offset = margin + cell * i as f64;
+            output.push_str(&format!(
+                "<line class=\"grid\" x1=\"{}\" y1=\"{o}\" x2=\"{}\" y2=\"{o}\"/>\n",
+                margin,
+                margin + board_width,
+                o = offset
+            ));
+        }
+
+        // the four traditional dots marking the corner regions, which small boards don't have
+        let stars = if size >= 8 { vec![(2, 2), (size - 2, 2), (2, size - 2), (size - 2, size - 2)] } else { Vec::new() };
+        for (x, y) in &stars {
+            output.push_str(&format!(
+                "<circle class=\"star\" cx=\"{}\" cy=\"{}\" r=\"{}\"/>\n",
+                margin + cell * *x as f64,
+                margin + cell * *y as f64,
+                cell / 16.0
+            ));
+        }
+
+        if self.coordinates {
+            for x in 0..size {
+                let label = (b'a' + x as u8) as char;
+                let cx = margin + cell * (x as f64 + 0.5);
+                for cy in &[margin / 2.0, height - margin / 2.0] {
+                    output.push_str(&self.text("coordinate", cx, *cy, &label.to_string()));
+                }
+            }
+            for y in 0..size {
+                let cy = margin + cell * (y as f64 + 0.5);
+                for cx in &[margin / 2.0, width - margin / 2.0] {
+                    output.push_str(&self.text("coordinate", *cx, cy, &(y + 1).to_string()));
+                }
+            }
+        }
+
+        let numbers = move_numbers(game);
+        let start = game.restart();
+        let board = if self.move_numbers { start.board() } else { game.board() };
+
+        for p in board.iter() {
+            let cx = margin + cell * (p.x as f64 + 0.5);
+            let cy = margin + cell * (p.y as f64 + 0.5);
+
+            let numbered = if self.move_numbers { numbers.get(&p) } else { None };
+
+            let disk = match (numbered, board.get(p.x, p.y)) {
+                (Some((_, disk)), _) => *disk,
+                (None, PositionState::Dark) => Disk::Dark,
+                (None, PositionState::Light) => Disk::Light,
+                (None, PositionState::Empty) => continue,
+                (None, PositionState::Blocked) => {
+                    output.push_str(&format!(
+                        "<rect class=\"blocked\" x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\"/>\n",
+                        margin + cell * p.x as f64,
+                        margin + cell * p.y as f64,
+                        cell,
+                        cell
+                    ));
+                    continue;
+                }
+            };
+
+            let (disc_class, text_class) = match disk {
+                Disk::Dark => ("dark", "on-dark"),
+                Disk::Light => ("light", "on-light"),
+            };
+
+            output.push_str(&format!(
+                "<circle class=\"disc {}\" cx=\"{}\" cy=\"{}\" r=\"{}\"/>\n",
+                disc_class,
+                cx,
+                cy,
+                cell * 0.42
+            ));
+
+            if let Some((number, _)) = numbered {
+                output.push_str(&self.text(&format!("number {}", text_class), cx, cy, &number.to_string()));
+            }
+        }
+
+        output.push_str("</svg>\n");
+        output
+    }
+
+    fn text(&self, class: &str, x: f64, y: f64, content: &str) -> String {
+        let font_size = match class {
+            "coordinate" => self.cell_size as f64 / 3.0,
+            _ => self.cell_size as f64 * 0.4,
+        };
+        format!(
+            "<text class=\"{}\" x=\"{}\" y=\"{}\" font-size=\"{}\">{}</text>\n",
+            class, x, y, font_size, content
+        )
+    }
+}
+
+impl Default for Svg {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transcript::{Transcript, MANUBU_MARUO};
+
+    #[test]
+    fn position_diagram() {
+        let g = Game::from_transcript(Transcript::from_string(MANUBU_MARUO));
+        let mut svg = Svg::new();
+        svg.highlights = vec![Position { x: 2, y: 4 }];
+
+        let output = svg.render(&g);
+        assert!(output.starts_with("<svg"));
+        assert!(output.ends_with("</svg>\n"));
+        assert_eq!(output.matches("class=\"disc dark\"").count(), 13);
+        assert_eq!(output.matches("class=\"disc light\"").count(), 0);
+        assert_eq!(output.matches("class=\"highlight\"").count(), 1);
+        assert_eq!(output.matches("class=\"coordinate\"").count(), 32);
+    }
+
+    #[test]
+    fn kifu_diagram() {
+        let g = Game::from_transcript(Transcript::from_string(MANUBU_MARUO));
+        let mut svg = Svg::new();
+        svg.move_numbers = true;
+        svg.coordinates = false;
+
+        // nine numbered moves (five by dark, four by light) over the four starting discs
+        let output = svg.render(&g);
+        assert_eq!(output.matches("class=\"number on-dark\"").count(), 5);
+        assert_eq!(output.matches("class=\"number on-light\"").count(), 4);
+        assert_eq!(output.matches("class=\"disc dark\"").count(), 7);
+        assert_eq!(output.matches("class=\"disc light\"").count(), 6);
+        assert!(!output.contains("coordinate\" x"));
+    }
+}
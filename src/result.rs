@@ -0,0 +1,128 @@
+use std::fmt;
+
+use crate::disk::Disk;
+use crate::rules::Rules;
+
+// How a finished game came out, as its rules decide the winner.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct GameResult {
+    // None for a draw
+    pub winner: Option<Disk>,
+    // dark's discs less light's, as they stand on the board
+    pub differential: i32,
+    // dark's and light's final scores: their discs, with any empty squares counted
+    // for the winner as under WOF rules, or shared in a draw
+    pub score: (usize, usize),
+}
+
+impl GameResult {
+    // The result from the discs each side has at the end, and the squares left empty.
+    pub fn new(discs: (usize, usize), empties: usize, rules: Rules) -> Self {
+        let (dark, light) = discs;
+        let differential = dark as i32 - light as i32;
+        let winner = match rules.outcome(differential).signum() {
+            1 => Some(Disk::Dark),
+            -1 => Some(Disk::Light),
+            _ => None,
+        };
+
+        // empties widen the margin of victory, so when the fewest discs win they
+        // count against the loser instead
+        let takes_empties = if rules.anti { winner.map(Disk::opposite) } else { winner };
+        let score = match takes_empties {
+            Some(Disk::Dark) => (dark + empties, light),
+            Some(Disk::Light) => (dark, light + empties),
+            None => (dark + empties / 2, light + empties - empties / 2),
+        };
+
+        GameResult { winner, differential, score }
+    }
+
+    // 1 if `disk` won, 0 for a draw and -1 if it lost.
+    pub fn outcome(&self, disk: Disk) -> i32 {
+        match self.winner {
+            None => 0,
+            Some(winner) if winner == disk => 1,
+            Some(_) => -1,
+        }
+    }
+
+    // The final score's margin for `disk`, negative when it lost.
+    pub fn margin(&self, disk: Disk) -> i32 {
+        let (dark, light) = self.score;
+        self.outcome(disk) * (dark as i32 - light as i32).abs()
+    }
+}
+
+impl fmt::Display for GameResult {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let (dark, light) = self.score;
+        match self.winner {
+            Some(winner) => write!(f, "{} wins {}-{}", winner, dark, light),
+            None => write!(f, "Draw {}-{}", dark, light),
+        }
+    }
+}
+
+// Wins, draws and losses over many games, for statistics.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Record {
+    pub games: usize,
+    pub dark_wins: usize,
+    pub draws: usize,
+    pub light_wins: usize,
+}
+
+impl Record {
+    pub fn new() -> Self {
+        Record::default()
+    }
+
+    // Counts a game, and its result if it has one; unfinished games only count as played.
+    pub fn add(&mut self, result: Option<GameResult>) {
+        self.games += 1;
+        match result.map(|r| r.winner) {
+            Some(Some(Disk::Dark)) => self.dark_wins += 1,
+            Some(Some(Disk::Light)) => self.light_wins += 1,
+            Some(None) => self.draws += 1,
+            None => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn results() {
+        // a win with empty squares left over takes them
+        let result = GameResult::new((40, 20), 4, Rules::new());
+        assert_eq!(result.winner, Some(Disk::Dark));
+        assert_eq!(result.differential, 20);
+        assert_eq!(result.score, (44, 20));
+        assert_eq!(result.margin(Disk::Dark), 24);
+        assert_eq!(result.margin(Disk::Light), -24);
+        assert_eq!(result.to_string(), "Dark wins 44-20");
+
+        let draw = GameResult::new((30, 30), 4, Rules::new());
+        assert_eq!(draw.winner, None);
+        assert_eq!(draw.score, (32, 32));
+        assert_eq!(draw.outcome(Disk::Light), 0);
+        assert_eq!(draw.to_string(), "Draw 32-32");
+
+        // in anti-reversi the fewest discs win, and the empties go to the loser
+        let anti = GameResult::new((40, 20), 4, Rules::anti());
+        assert_eq!(anti.winner, Some(Disk::Light));
+        assert_eq!(anti.score, (44, 20));
+        assert_eq!(anti.margin(Disk::Light), 24);
+
+        let mut record = Record::new();
+        record.add(Some(result));
+        record.add(Some(draw));
+        record.add(Some(anti));
+        record.add(None);
+        assert_eq!(record, Record { games: 4, dark_wins: 1, draws: 1, light_wins: 1 });
+    }
+}
@@ -0,0 +1,112 @@
+use std::fmt;
+
+// The variant being played. Everything that decides who won, from final scores
+// through search and evaluation to match results, goes through the game's rules.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+pub struct Rules {
+    // anti-reversi, also called misère: the player with the fewest discs wins
+    pub anti: bool,
+    // the original 1880s opening: the centre starts empty and the first four moves
+    // place discs there without flipping anything
+    pub placement: bool,
+    // how many discs each player has to play with
+    pub supply: Supply,
+}
+
+// Historical Reversi gave each player 32 discs. A player who runs out either has
+// to pass, or is handed the discs they need by the opponent, as in Othello.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Supply {
+    #[default]
+    Unlimited,
+    Pass(usize),
+    HandOver(usize),
+}
+
+impl Supply {
+    // the discs each player starts with, if they're limited
+    pub fn discs(&self) -> Option<usize> {
+        match *self {
+            Supply::Unlimited => None,
+            Supply::Pass(n) | Supply::HandOver(n) => Some(n),
+        }
+    }
+}
+
+impl Rules {
+    pub fn new() -> Self {
+        Rules {
+            anti: false,
+            placement: false,
+            supply: Supply::Unlimited,
+        }
+    }
+
+    pub fn anti() -> Self {
+        Rules { anti: true, ..Rules::new() }
+    }
+
+    // Reversi as first published: placed centre discs, and 32 discs a side.
+    pub fn historical() -> Self {
+        Rules {
+            placement: true,
+            supply: Supply::Pass(32),
+            ..Rules::new()
+        }
+    }
+
+    pub fn is_standard(&self) -> bool {
+        *self == Rules::new()
+    }
+
+    // A disc differential, as it counts towards winning: negated when having fewer
+    // discs is what wins.
+    pub fn outcome(&self, differential: i32) -> i32 {
+        if self.anti {
+            -differential
+        } else {
+            differential
+        }
+    }
+}
+
+impl fmt::Display for Rules {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut parts = Vec::new();
+        if self.anti {
+            parts.push("Anti-reversi".to_string());
+        }
+        if self.placement {
+            parts.push("Placed centre".to_string());
+        }
+        match self.supply {
+            Supply::Unlimited => {}
+            Supply::Pass(n) => parts.push(format!("{} discs each, passing when out", n)),
+            Supply::HandOver(n) => parts.push(format!("{} discs each, handed over when out", n)),
+        }
+        if parts.is_empty() {
+            write!(f, "Standard")
+        } else {
+            write!(f, "{}", parts.join(", "))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn outcomes() {
+        assert!(Rules::default().is_standard());
+        assert_eq!(Rules::new().outcome(6), 6);
+        assert_eq!(Rules::anti().outcome(6), -6);
+        assert!(!Rules::anti().is_standard());
+        assert_eq!(Rules::anti().to_string(), "Anti-reversi");
+        assert_eq!(Rules::historical().to_string(), "Placed centre, 32 discs each, passing when out");
+        assert_eq!(Rules::historical().supply.discs(), Some(32));
+    }
+}
@@ -0,0 +1,316 @@
+use std::fmt;
+
+use serde::de::{self, Deserializer, Visitor};
+use serde::ser::Serializer;
+use serde::{Deserialize, Serialize};
+
+use crate::board::{Board, MAX_SIZE, MIN_SIZE, SIZE};
+use crate::disk::Disk;
+use crate::game::Game;
+use crate::position::Position;
+use crate::rules::Rules;
+use crate::transcript::Transcript;
+
+// Serde support, behind the `serde` feature. Everything is written the way people
+// read and write games: positions as "E6", moves as "E6" or "PP", move lists as
+// transcript strings and boards as rows of D, L and "." for empty squares.
+
+// Columns run from A to P and rows from 1 to 16, to cover every board size.
+fn parse_position(s: &str) -> Option<Position> {
+    let mut chars = s.chars();
+    let x = chars.next()?.to_ascii_uppercase();
+    let digits = chars.as_str();
+    if !digits.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    let y: usize = digits.parse().ok()?;
+    if !('A'..='P').contains(&x) || !(1..=MAX_SIZE).contains(&y) {
+        return None;
+    }
+    Some(Position {
+        x: x as usize - 'A' as usize,
+        y: y - 1,
+    })
+}
+
+fn parse_transcript(s: &str) -> Option<Transcript> {
+    if s.eq_ignore_ascii_case("PP") {
+        return Some(Transcript::Pass);
+    }
+    parse_position(s).map(Transcript::Play)
+}
+
+// A string visitor that parses with `parse`, describing what it expects as `expecting`.
+struct Parse<T> {
+    expecting: &'static str,
+    parse: fn(&str) -> Option<T>,
+}
+
+impl<'de, T> Visitor<'de> for Parse<T> {
+    type Value = T;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.expecting)
+    }
+
+    fn visit_str<E: de::Error>(self, v: &str) -> Result<T, E> {
+        (self.parse)(v).ok_or_else(|| E::invalid_value(de::Unexpected::Str(v), &self))
+    }
+}
+
+impl Serialize for Position {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(&Transcript::Play(*self))
+    }
+}
+
+impl<'de> Deserialize<'de> for Position {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_str(Parse {
+            expecting: "a square from A1 to P16",
+            parse: parse_position,
+        })
+    }
+}
+
+impl Serialize for Transcript {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for Transcript {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_str(Parse {
+            expecting: "a square from A1 to P16, or PP for a pass",
+            parse: parse_transcript,
+        })
+    }
+}
+
+// For `#[serde(with = "crate::serialization::transcripts")]` on a list of moves,
+// which is then written as a single transcript string like "F5D6C3".
+pub mod transcripts {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(transcripts: &[Transcript], serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(&Transcript::stringify(transcripts))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<Transcript>, D::Error> {
+        deserializer.deserialize_str(Parse {
+            expecting: "a transcript like F5D6C3",
+            parse: |s| {
+                // each move starts at a letter, and rows can take two digits
+                if !s.is_ascii() {
+                    return None;
+                }
+                let mut moves = Vec::new();
+                let mut i = 0;
+                while i < s.len() {
+                    let end = if s[i..].len() >= 2 && s[i..i + 2].eq_ignore_ascii_case("PP") {
+                        i + 2
+                    } else {
+                        s[i + 1..].find(|c: char| !c.is_ascii_digit()).map_or(s.len(), |n| i + 1 + n)
+                    };
+                    moves.push(parse_transcript(&s[i..end])?);
+                    i = end;
+                }
+                Some(moves)
+            },
+        })
+    }
+}
+
+impl Serialize for Board {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.rows().serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Board {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        // the board's size is its number of rows
+        let rows = Vec::<String>::deserialize(deserializer)?;
+        Board::from_rows(&rows).ok_or_else(|| {
+            de::Error::custom("a board is 4 to 16 rows of ., D, L and #, each as long as the board is tall")
+        })
+    }
+}
+
+// Games are written with their board and turn for the reader's convenience, but
+// read back by replaying the transcript; a board or turn that's given has to match.
+// The size and layout are only written for games that don't use the standard ones.
+#[derive(Serialize, Deserialize)]
+struct GameData {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    size: Option<usize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    layout: Option<Board>,
+    #[serde(default, skip_serializing_if = "Rules::is_standard")]
+    rules: Rules,
+    #[serde(with = "transcripts")]
+    transcript: Vec<Transcript>,
+    #[serde(default)]
+    turn: Option<Disk>,
+    #[serde(default)]
+    board: Option<Board>,
+}
+
+impl Serialize for Game {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let size = self.board().size();
+        GameData {
+            size: if size == SIZE { None } else { Some(size) },
+            layout: if self.has_standard_layout() { None } else { Some(self.layout()) },
+            rules: self.rules(),
+            transcript: self.transcript.clone(),
+            turn: Some(self.turn),
+            board: Some(*self.board()),
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Game {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let data = GameData::deserialize(deserializer)?;
+
+        let size = data.size.or(data.layout.map(|b| b.size())).or(data.board.map(|b| b.size())).unwrap_or(SIZE);
+        let start = match data.layout {
+            Some(layout) if layout.size() == size => Game::from_layout(layout, data.rules),
+            Some(_) => return Err(de::Error::custom("layout doesn't match the board size")),
+            None if size.is_multiple_of(2) && (MIN_SIZE..=MAX_SIZE).contains(&size) => Game::with_rules(size, data.rules),
+            None => return Err(de::Error::custom(format!("unsupported board size {}", size))),
+        };
+        let game = start
+            .try_replay(&data.transcript, false)
+            .map_err(|e| de::Error::custom(format!("invalid transcript: {}", e)))?;
+
+        if data.turn.is_some_and(|turn| turn != game.turn) {
+            return Err(de::Error::custom("turn doesn't match the transcript"));
+        }
+        if data.board.is_some_and(|board| board != *game.board()) {
+            return Err(de::Error::custom("board doesn't match the transcript"));
+        }
+        Ok(game)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::position::PositionState;
+    use crate::solvers::alphabeta::AlphaBeta;
+    use crate::eval::heuristic::Parity;
+
+    #[test]
+    fn positions_and_moves() {
+        let e6 = Position { x: 4, y: 5 };
+        assert_eq!(serde_json::to_string(&e6).unwrap(), "\"E6\"");
+        assert_eq!(serde_json::from_str::<Position>("\"e6\"").unwrap(), e6);
+        assert!(serde_json::from_str::<Position>("\"Q9\"").is_err());
+
+        let moves = vec![Transcript::Play(e6), Transcript::Pass];
+        assert_eq!(serde_json::to_string(&moves).unwrap(), "[\"E6\",\"PP\"]");
+        assert_eq!(serde_json::from_str::<Vec<Transcript>>("[\"E6\",\"PP\"]").unwrap(), moves);
+
+        assert_eq!(serde_json::to_string(&Disk::Dark).unwrap(), "\"Dark\"");
+        assert_eq!(serde_json::to_string(&PositionState::Empty).unwrap(), "\"Empty\"");
+    }
+
+    #[test]
+    fn games() {
+        let game = Game::from_transcript(Transcript::from_string("F5D6C3"));
+        let json = serde_json::to_value(&game).unwrap();
+        assert_eq!(json["transcript"], "F5D6C3");
+        assert!(json.get("size").is_none());
+        assert_eq!(json["turn"], "Light");
+        assert_eq!(json["board"][2], "..D.....");
+        assert_eq!(json["board"][4], "...LDD..");
+
+        let read: Game = serde_json::from_value(json).unwrap();
+        assert_eq!(read.transcript, game.transcript);
+        assert_eq!(read.board(), game.board());
+
+        // the board and turn can be left out, but not contradict the transcript
+        let short: Game = serde_json::from_str("{\"transcript\": \"F5D6\"}").unwrap();
+        assert_eq!(short.turn, Disk::Dark);
+        assert!(serde_json::from_str::<Game>("{\"transcript\": \"F5D6\", \"turn\": \"Light\"}").is_err());
+        assert!(serde_json::from_str::<Game>("{\"transcript\": \"F5F5\"}").is_err());
+    }
+
+    #[test]
+    fn other_sizes() {
+        assert_eq!(serde_json::from_str::<Position>("\"J10\"").unwrap(), Position { x: 9, y: 9 });
+        assert!(serde_json::from_str::<Position>("\"J17\"").is_err());
+
+        let game = Game::from_transcript_with_size(10, Transcript::from_string("G6E7"));
+        let json = serde_json::to_value(&game).unwrap();
+        assert_eq!(json["size"], 10);
+        assert_eq!(json["transcript"], "G6E7");
+        assert_eq!(json["board"][6], "....L.....");
+
+        let read: Game = serde_json::from_value(json).unwrap();
+        assert_eq!(read.board(), game.board());
+        let read: Game = serde_json::from_str("{\"size\": 6, \"transcript\": \"C2\"}").unwrap();
+        assert_eq!(read.board().size(), 6);
+        assert!(serde_json::from_str::<Game>("{\"size\": 7, \"transcript\": \"\"}").is_err());
+    }
+
+    #[test]
+    fn layouts() {
+        let mut board = crate::layout::cross(8);
+        board.set(0, 0, PositionState::Blocked);
+        let game = Game::from_layout(board, Rules::new()).replay(Transcript::from_string("E3"));
+        let json = serde_json::to_value(&game).unwrap();
+        assert_eq!(json["layout"][0], "#.......");
+        assert_eq!(json["layout"][3], "...LL...");
+
+        let read: Game = serde_json::from_value(json).unwrap();
+        assert_eq!(read.layout(), board);
+        assert_eq!(read.board(), game.board());
+        assert!(serde_json::from_str::<Board>("[\"....\", \"..x.\", \"....\", \"....\"]").is_err());
+
+        // an odd-sized layout has nothing to compare with the standard start
+        let odd = "{\"layout\": [\".....\", \".LD..\", \".DL..\", \".....\", \".....\"], \"transcript\": \"B1\"}";
+        let read: Game = serde_json::from_str(odd).unwrap();
+        assert_eq!(read.board().size(), 5);
+        assert_eq!(read.transcript.len(), 1);
+    }
+
+    #[test]
+    fn rules() {
+        let game = Game::with_rules(8, Rules::anti()).replay(Transcript::from_string("F5"));
+        let json = serde_json::to_value(&game).unwrap();
+        assert_eq!(json["rules"]["anti"], true);
+        assert_eq!(serde_json::from_value::<Game>(json).unwrap().rules(), Rules::anti());
+
+        let read: Game = serde_json::from_str("{\"transcript\": \"F5\", \"rules\": {}}").unwrap();
+        assert!(read.rules().is_standard());
+
+        // placed centre discs replay from the empty centre, and the supply is rebuilt
+        let game = Game::with_rules(8, Rules::historical()).replay(Transcript::from_string("D4E4E5D5E3"));
+        let json = serde_json::to_value(&game).unwrap();
+        assert_eq!(json["rules"]["supply"]["Pass"], 32);
+        assert!(json.get("layout").is_none());
+        let read: Game = serde_json::from_value(json).unwrap();
+        assert_eq!(read.board(), game.board());
+        assert_eq!(read.supply(), Some((29, 30)));
+    }
+
+    #[test]
+    fn search_results() {
+        let game = Game::from_transcript(Transcript::from_string("E6F4E3F6G5D6E7F5"));
+        let result = AlphaBeta::new(Parity, 3).search(&game);
+
+        let json = serde_json::to_value(&result).unwrap();
+        assert_eq!(json["best"], "C5");
+        assert_eq!(json["pv"], "C5");
+        assert_eq!(json["score"], 1300);
+
+        let vm = &game.valid_moves()[0];
+        let json = serde_json::to_value(vm).unwrap();
+        assert_eq!(json["position"], serde_json::to_value(vm.position).unwrap());
+    }
+}
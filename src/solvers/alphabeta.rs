@@ -0,0 +1,356 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Instant;
+
+use crate::eval::{final_score, Evaluator, DISC};
+use crate::game::Game;
+use crate::solvers::table::{game_hash, Bound, Entry, TranspositionTable};
+use crate::transcript::Transcript;
+
+// fixed depth negamax search with alpha-beta pruning
+
+const INFINITY: i32 = i32::MAX - 1;
+
+// how many nodes to search between checks of the stop flag and deadline
+const CHECK_INTERVAL: u64 = 1024;
+
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SearchResult {
+    // None if the game is already complete
+    pub best: Option<Transcript>,
+    // from the point of view of the player to move
+    pub score: i32,
+    // principal variation, starting with the best move
+    #[cfg_attr(feature = "serde", serde(with = "crate::serialization::transcripts"))]
+    pub pv: Vec<Transcript>,
+    pub nodes: u64,
+}
+
+pub struct AlphaBeta<E: Evaluator> {
+    pub evaluator: E,
+    pub depth: usize,
+    // setting this, from any thread, abandons the search in progress
+    pub stop: Arc<AtomicBool>,
+    pub deadline: Option<Instant>,
+    // shared between searches and threads, if set
+    pub table: Option<Arc<TranspositionTable>>,
+    nodes: u64,
+    aborted: bool,
+}
+
+impl<E: Evaluator> AlphaBeta<E> {
+    pub fn new(evaluator: E, depth: usize) -> Self {
+        Self {
+            evaluator,
+            depth,
+            stop: Arc::new(AtomicBool::new(false)),
+            deadline: None,
+            table: None,
+            nodes: 0,
+            aborted: false,
+        }
+    }
+
+    // true if the last search was stopped or ran past its deadline, in which
+    // case its result is meaningless.
+    pub fn aborted(&self) -> bool {
+        self.aborted
+    }
+
+    fn should_stop(&mut self) -> bool {
+        if !self.aborted && self.nodes.is_multiple_of(CHECK_INTERVAL) {
+            let late = match self.deadline {
+                Some(deadline) => Instant::now() >= deadline,
+                None => false,
+            };
+            self.aborted = late || self.stop.load(Ordering::Relaxed);
+        }
+        self.aborted
+    }
+
+    pub fn search(&mut self, game: &Game) -> SearchResult {
+        self.nodes = 0;
+        self.aborted = false;
+        let mut pv = Vec::new();
+        let score = self.negamax(game, self.depth, -INFINITY, INFINITY, &mut pv);
+
+        SearchResult {
+            best: pv.first().copied(),
+            score,
+            pv,
+            nodes: self.nodes,
+        }
+    }
+
+    fn negamax(&mut self, game: &Game, depth: usize, mut alpha: i32, beta: i32, pv: &mut Vec<Transcript>) -> i32 {
+        self.nodes += 1;
+        pv.clear();
+
+        if self.should_stop() {
+            return 0;
+        }
+
+        let moves = game.valid_moves();
+
+        if moves.is_empty() {
+            // neither side can move, so the game is over
+            if game.moves_for(game.turn.opposite()).next().is_none() {
+                return final_score(game, game.turn);
+            }
+            if depth == 0 {
+                return self.evaluator.evaluate(game, game.turn);
+            }
+
+            // passing doesn't use up depth, so searching as deep as there are empty
+            // squares always reaches the end of the game
+            let mut child_pv = Vec::new();
+            let score = -self.negamax(&game.pass(), depth, -beta, -alpha, &mut child_pv);
+            pv.push(Transcript::Pass);
+            pv.append(&mut child_pv);
+            return score;
+        }
+
+        if depth == 0 {
+            return self.evaluator.evaluate(game, game.turn);
+        }
+
+        // When the search reaches the end of the game every score is an exact disc
+        // differential, so the opponent's stable discs bound how well we can do; or
+        // our own, when the fewest discs win.
+        let empties = game.board().empties();
+        if depth >= empties {
+            let bound = if game.rules().anti { game.turn } else { game.turn.opposite() };
+            let stable = game.board().stable_discs(bound).len() as i32;
+            let (dark, light) = game.score();
+            let ceiling = ((dark + light + empties) as i32 - 2 * stable) * DISC;
+            if ceiling <= alpha {
+                return ceiling;
+            }
+        }
+
+        // reuse what earlier searches, or other threads, found for this position
+        let mut moves = moves;
+        let key = self.table.as_ref().map(|_| game_hash(game));
+        if let (Some(table), Some(key)) = (&self.table, key) {
+            if let Some(entry) = table.probe(key) {
+                let root = depth == self.depth;
+                if entry.depth >= depth && !root {
+                    let cutoff = match entry.bound {
+                        Bound::Exact => true,
+                        Bound::Lower => entry.score >= beta,
+                        Bound::Upper => entry.score <= alpha,
+                    };
+                    if cutoff {
+                        pv.extend(entry.best);
+                        return entry.score;
+                    }
+                }
+
+                // the best move last time is likely to be best again, so try it first
+                if let Some(Transcript::Play(p)) = entry.best {
+                    if let Some(index) = moves.iter().position(|vm| vm.position == p) {
+                        let vm = moves.remove(index);
+                        moves.insert(0, vm);
+                    }
+                }
+            }
+        }
+
+        let original_alpha = alpha;
+        let mut best = -INFINITY;
+        let mut child_pv = Vec::new();
+
+        for vm in moves {
+            let position = vm.position;
+            let score = -self.negamax(&game.play(vm), depth - 1, -beta, -alpha, &mut child_pv);
+
+            if score > best {
+                best = score;
+                pv.clear();
+                pv.push(Transcript::Play(position));
+                pv.append(&mut child_pv);
+            }
+            if best > alpha {
+                alpha = best;
+            }
+            if alpha >= beta {
+                break;
+            }
+        }
+
+        if let (Some(table), Some(key)) = (&self.table, key) {
+            if !self.aborted {
+                let bound = if best <= original_alpha {
+                    Bound::Upper
+                } else if best >= beta {
+                    Bound::Lower
+                } else {
+                    Bound::Exact
+                };
+                table.store(
+                    key,
+                    Entry {
+                        depth,
+                        score: best,
+                        bound,
+                        best: pv.first().copied(),
+                    },
+                );
+            }
+        }
+
+        best
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::eval::heuristic::{Heuristic, Parity};
+    use crate::solvers::random::{Random, Seed};
+    use crate::rules::{Rules, Supply};
+
+    // plain minimax, to check that pruning never changes the result
+    fn minimax<E: Evaluator>(evaluator: &E, game: &Game, depth: usize) -> i32 {
+        let moves = game.valid_moves();
+        if moves.is_empty() {
+            if game.is_complete() {
+                return final_score(game, game.turn);
+            }
+            if depth == 0 {
+                return evaluator.evaluate(game, game.turn);
+            }
+            return -minimax(evaluator, &game.pass(), depth);
+        }
+        if depth == 0 {
+            return evaluator.evaluate(game, game.turn);
+        }
+        moves.into_iter().map(|vm| -minimax(evaluator, &game.play(vm), depth - 1)).max().unwrap()
+    }
+
+    #[test]
+    fn matches_minimax() {
+        let games = vec![
+            Game::new(),
+            Game::from_transcript(Transcript::from_string("F5D6C3D3C4F4")),
+            Game::from_transcript(Transcript::from_string("E6F4E3F6G5D6E7")),
+            Game::with_rules(8, Rules::historical()),
+        ];
+
+        for g in games {
+            for depth in 1..=4 {
+                let mut search = AlphaBeta::new(Heuristic::new(), depth);
+                assert_eq!(search.search(&g).score, minimax(&Heuristic::new(), &g, depth));
+            }
+        }
+    }
+
+    #[test]
+    fn exact_endgame() {
+        // the last few moves of a random game, solved exactly with and without the stability cutoff
+        let full = Random::new(Game::new(), Seed::from_string("endgame".to_string())).next().unwrap();
+        let g = Game::from_transcript(full.transcript[..full.transcript.len() - 8].to_vec());
+        let exact = minimax(&Parity, &g, 64);
+        let mut search = AlphaBeta::new(Heuristic::new(), 64);
+        assert_eq!(search.search(&g).score, exact);
+        assert_eq!(exact % DISC, 0);
+    }
+
+    #[test]
+    fn limited_supplies() {
+        // games that end when the discs run out, with empty squares left, are still solved exactly
+        for supply in [Supply::Pass(5), Supply::HandOver(5)] {
+            let g = Game::with_rules(4, Rules { placement: true, supply, ..Rules::new() });
+            let exact = minimax(&Parity, &g, 16);
+            let mut search = AlphaBeta::new(Heuristic::new(), 16);
+            search.table = Some(Arc::new(TranspositionTable::new(1)));
+            assert_eq!(search.search(&g).score, exact);
+        }
+    }
+
+    #[test]
+    fn small_boards() {
+        // 4x4 is small enough to check against plain minimax; light wins 11-3
+        let g = Game::with_size(4);
+        let exact = minimax(&Parity, &g, 16);
+        assert_eq!(exact, -8 * DISC);
+        assert_eq!(AlphaBeta::new(Heuristic::new(), 16).search(&g).score, exact);
+
+        // the stability cutoff on a 6x6 endgame
+        let full = Random::new(Game::with_size(6), Seed::from_string("six".to_string())).next().unwrap();
+        let g = Game::from_transcript_with_size(6, full.transcript[..full.transcript.len() - 8].to_vec());
+        assert_eq!(AlphaBeta::new(Heuristic::new(), 36).search(&g).score, minimax(&Parity, &g, 36));
+    }
+
+    #[test]
+    fn anti_endgame() {
+        // the stability cutoff has to bound the score by our own discs instead
+        let full = Random::new(Game::with_rules(8, Rules::anti()), Seed::from_string("anti".to_string())).next().unwrap();
+        let g = full.restart().replay(full.transcript[..full.transcript.len() - 8].to_vec());
+        let exact = minimax(&Parity, &g, 64);
+        assert_eq!(AlphaBeta::new(Heuristic::new(), 64).search(&g).score, exact);
+
+        // 4x4 anti-reversi is a win for dark, by three discs
+        let g = Game::with_rules(4, Rules::anti());
+        let exact = minimax(&Parity, &g, 16);
+        assert_eq!(exact, 3 * DISC);
+        assert_eq!(AlphaBeta::new(Parity, 16).search(&g).score, exact);
+    }
+
+    #[test]
+    fn transposition_table() {
+        let full = Random::new(Game::new(), Seed::from_string("table".to_string())).next().unwrap();
+        let g = Game::from_transcript(full.transcript[..full.transcript.len() - 10].to_vec());
+
+        let mut plain = AlphaBeta::new(Heuristic::new(), 64);
+        let mut cached = AlphaBeta::new(Heuristic::new(), 64);
+        cached.table = Some(Arc::new(TranspositionTable::new(1)));
+
+        let expected = plain.search(&g);
+        let first = cached.search(&g);
+        assert_eq!(first.score, expected.score);
+        assert!(first.nodes <= expected.nodes);
+
+        // a second search starts from everything the first one learned
+        let second = cached.search(&g);
+        assert_eq!(second.score, expected.score);
+        assert!(second.nodes < first.nodes);
+        assert_eq!(second.best, first.best);
+    }
+
+    #[test]
+    fn finds_the_wipeout() {
+        // one move from Manubu Maruo's win; C5 takes every light disc
+        let g = Game::from_transcript(Transcript::from_string("E6F4E3F6G5D6E7F5"));
+        let mut search = AlphaBeta::new(Parity, 3);
+        let result = search.search(&g);
+
+        assert_eq!(result.best, Some(Transcript::from_string("C5")[0]));
+        assert_eq!(result.score, 13 * DISC);
+        assert_eq!(result.pv.len(), 1);
+        assert!(result.nodes > 1);
+    }
+
+    #[test]
+    fn stops() {
+        let mut search = AlphaBeta::new(Heuristic::new(), 20);
+        search.stop.store(true, std::sync::atomic::Ordering::Relaxed);
+        search.search(&Game::new());
+        assert!(search.aborted());
+
+        search.stop.store(false, std::sync::atomic::Ordering::Relaxed);
+        search.depth = 2;
+        search.deadline = Some(Instant::now() + std::time::Duration::from_secs(60));
+        search.search(&Game::new());
+        assert!(!search.aborted());
+    }
+
+    #[test]
+    fn complete_game() {
+        let g = Game::from_transcript(Transcript::from_string("E6F4E3F6G5D6E7F5C5"));
+        let result = AlphaBeta::new(Parity, 3).search(&g);
+        assert_eq!(result.best, None);
+        assert_eq!(result.score, -13 * DISC);
+    }
+}
@@ -0,0 +1,175 @@
+use std::fmt;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use crate::eval::{Evaluator, DISC};
+use crate::game::Game;
+use crate::solvers::alphabeta::{AlphaBeta, SearchResult};
+use crate::solvers::table::TranspositionTable;
+use crate::transcript::Transcript;
+
+// iterative deepening over the alpha-beta searcher, within a time budget
+
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Iteration {
+    pub depth: usize,
+    pub result: SearchResult,
+    // nodes over all iterations so far
+    pub nodes: u64,
+    pub elapsed: Duration,
+}
+
+impl Iteration {
+    pub fn nps(&self) -> u64 {
+        (self.nodes as f64 / self.elapsed.as_secs_f64().max(1e-6)) as u64
+    }
+}
+
+impl fmt::Display for Iteration {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "depth {} score {:+.2} nodes {} nps {} time {:?} pv {}",
+            self.depth,
+            self.result.score as f64 / DISC as f64,
+            self.nodes,
+            self.nps(),
+            self.elapsed,
+            Transcript::stringify(&self.result.pv)
+        )
+    }
+}
+
+pub struct Deepening<E: Evaluator> {
+    pub start_depth: usize,
+    pub max_depth: usize,
+    searcher: AlphaBeta<E>,
+}
+
+impl<E: Evaluator> Deepening<E> {
+    pub fn new(evaluator: E) -> Self {
+        Self {
+            start_depth: 1,
+            max_depth: 60,
+            searcher: AlphaBeta::new(evaluator, 1),
+        }
+    }
+
+    // Setting the returned flag from another thread ends the search in progress,
+    // which then returns its deepest completed iteration. The flag is cleared
+    // when the search returns.
+    pub fn stop_handle(&self) -> Arc<AtomicBool> {
+        self.searcher.stop.clone()
+    }
+
+    pub fn set_table(&mut self, table: Arc<TranspositionTable>) {
+        self.searcher.table = Some(table);
+    }
+
+    // Searches one ply deeper at a time until the budget runs out, the game is
+    // solved to the end, or `max_depth` is reached, calling `report` after each
+    // completed iteration.
+    pub fn search<F: FnMut(&Iteration)>(&mut self, game: &Game, budget: Option<Duration>, mut report: F) -> Iteration {
+        let start = Instant::now();
+
+        let empties = game.board().empties();
+
+        let mut nodes = 0;
+        let mut completed: Option<Iteration> = None;
+
+        for depth in self.start_depth.max(1)..=self.max_depth.max(1) {
+            self.searcher.depth = depth;
+            self.searcher.deadline = match (budget, &completed) {
+                (Some(b), Some(_)) => Some(start + b),
+                _ => None,
+            };
+
+            let result = if completed.is_none() {
+                // nothing stops the first iteration, so there's always a move to play
+                let stop = std::mem::replace(&mut self.searcher.stop, Arc::new(AtomicBool::new(false)));
+                let result = self.searcher.search(game);
+                self.searcher.stop = stop;
+                result
+            } else {
+                let result = self.searcher.search(game);
+                if self.searcher.aborted() {
+                    break;
+                }
+                result
+            };
+
+            nodes += result.nodes;
+            let iteration = Iteration {
+                depth,
+                result,
+                nodes,
+                elapsed: start.elapsed(),
+            };
+            report(&iteration);
+            completed = Some(iteration);
+
+            // searching past the end of the game changes nothing
+            if depth >= empties {
+                break;
+            }
+
+            // the next iteration would almost certainly not finish in time
+            if let Some(b) = budget {
+                if start.elapsed() > b / 2 {
+                    break;
+                }
+            }
+        }
+
+        self.searcher.stop.store(false, Ordering::Relaxed);
+        completed.expect("at least one iteration always completes")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::eval::heuristic::Heuristic;
+    use std::thread;
+
+    #[test]
+    fn deepens() {
+        let mut deepening = Deepening::new(Heuristic::new());
+        deepening.max_depth = 4;
+
+        let mut depths = Vec::new();
+        let last = deepening.search(&Game::new(), None, |i| depths.push(i.depth));
+        assert_eq!(depths, vec![1, 2, 3, 4]);
+        assert_eq!(last.depth, 4);
+        assert_eq!(last.result, AlphaBeta::new(Heuristic::new(), 4).search(&Game::new()));
+    }
+
+    #[test]
+    fn respects_budget() {
+        let mut deepening = Deepening::new(Heuristic::new());
+        let budget = Duration::from_millis(50);
+        let start = Instant::now();
+        let last = deepening.search(&Game::new(), Some(budget), |_| ());
+
+        assert!(start.elapsed() < budget * 4);
+        assert!(last.result.best.is_some());
+    }
+
+    #[test]
+    fn stops_from_another_thread() {
+        let mut deepening = Deepening::new(Heuristic::new());
+        let stop = deepening.stop_handle();
+        let stopper = thread::spawn(move || {
+            thread::sleep(Duration::from_millis(50));
+            stop.store(true, Ordering::Relaxed);
+        });
+
+        let last = deepening.search(&Game::new(), None, |_| ());
+        stopper.join().unwrap();
+
+        assert!(last.depth < 60);
+        assert!(last.result.best.is_some());
+    }
+}
@@ -16,6 +16,7 @@ impl Incremental {
         Self { index }
     }
 
+    #[allow(clippy::collapsible_match)]
     fn trim(&mut self) {
         loop {
             match self.index.last() {
@@ -34,6 +35,7 @@ impl Incremental {
 
 impl Iterator for Incremental {
     type Item = Game;
+    #[allow(clippy::question_mark)]
     fn next(&mut self) -> Option<Self::Item> {
         loop {
             let mut current_node = match self.index.pop() {
@@ -43,14 +45,17 @@ impl Iterator for Incremental {
 
             match current_node.valid_moves.pop() {
                 None => {
-                    // no moves remaining
-                    if current_node.game.is_complete() {
-                        // revert up the stack until we find a node with available moves.
-                        self.trim();
-                        return Some(current_node.game);
-                    } else {
-                        let new_game = current_node.game.pass();
-                        self.index.push(Node::new(&new_game))
+                    // no moves remaining, so the game is over or the only action is a pass
+                    match current_node.game.legal_actions().pop() {
+                        None => {
+                            // revert up the stack until we find a node with available moves.
+                            self.trim();
+                            return Some(current_node.game);
+                        }
+                        Some(pass) => {
+                            let new_game = current_node.game.apply(pass).expect("a forced pass");
+                            self.index.push(Node::new(&new_game))
+                        }
                     }
                 }
                 Some(valid_move) => {
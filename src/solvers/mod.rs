@@ -1,5 +1,9 @@
+pub mod alphabeta;
+pub mod deepening;
 pub mod incremental;
 pub mod random;
+pub mod smp;
+pub mod table;
 pub mod parallel;
 
 use crate::game::{Game, ValidMove};
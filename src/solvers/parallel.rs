@@ -37,3 +37,9 @@ impl Parallel {
     }
 }
 
+impl Default for Parallel {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
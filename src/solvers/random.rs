@@ -1,4 +1,5 @@
 use crate::game::Game;
+use crate::transcript::Transcript;
 use rand::prelude::*;
 use sha2::{Digest, Sha256};
 
@@ -26,6 +27,16 @@ impl Seed {
 
         Self { string, value }
     }
+
+    pub fn rng(&self) -> StdRng {
+        StdRng::from_seed(self.value)
+    }
+}
+
+impl Default for Seed {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 pub struct Random {
@@ -37,23 +48,20 @@ impl Random {
     pub fn new(game: Game, seed: Seed) -> Self {
         Self {
             game,
-            rng: StdRng::from_seed(seed.value),
+            rng: seed.rng(),
         }
     }
 
     fn solve(&mut self) -> Game {
         let mut g = self.game.clone();
         loop {
-            if g.is_complete() {
-                return g;
-            }
-
-            let valid_moves = g.valid_moves();
-
-            g = match valid_moves.choose(&mut self.rng) {
-                None => g.pass(),
-                Some(vm) => g.play(vm.clone()),
-            }
+            // a forced pass isn't a choice, so it doesn't draw on the generator
+            let t = match g.legal_actions().as_slice() {
+                [] => return g,
+                [Transcript::Pass] => Transcript::Pass,
+                moves => *moves.choose(&mut self.rng).unwrap(),
+            };
+            g = g.apply(t).expect("legal actions can be applied");
         }
     }
 }
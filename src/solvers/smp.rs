@@ -0,0 +1,132 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+use crate::eval::Evaluator;
+use crate::game::Game;
+use crate::solvers::deepening::{Deepening, Iteration};
+use crate::solvers::table::TranspositionTable;
+
+// Lazy SMP: every thread runs its own iterative deepening search of the same
+// position, sharing one transposition table. Helpers fill the table with results
+// the main thread then finds instead of searching; odd helpers run a ply ahead
+// so the threads don't all walk the tree in lockstep.
+
+pub struct Smp<E: Evaluator + Clone + Send> {
+    pub max_depth: usize,
+    // nodes each helper searched in the last search
+    pub helper_nodes: Vec<u64>,
+    main: Deepening<E>,
+    helpers: Vec<Deepening<E>>,
+}
+
+impl<E: Evaluator + Clone + Send> Smp<E> {
+    pub fn new(evaluator: E, threads: usize, table_megabytes: usize) -> Self {
+        let table = Arc::new(TranspositionTable::new(table_megabytes));
+        let deepening = |index: usize| {
+            let mut deepening = Deepening::new(evaluator.clone());
+            deepening.start_depth = 1 + index % 2;
+            deepening.set_table(table.clone());
+            deepening
+        };
+
+        Self {
+            max_depth: 60,
+            helper_nodes: Vec::new(),
+            main: deepening(0),
+            helpers: (1..threads.max(1)).map(deepening).collect(),
+        }
+    }
+
+    pub fn threads(&self) -> usize {
+        self.helpers.len() + 1
+    }
+
+    // stops the whole search, as with `Deepening::stop_handle`
+    pub fn stop_handle(&self) -> Arc<AtomicBool> {
+        self.main.stop_handle()
+    }
+
+    // Same as `Deepening::search`; iterations are reported from the main thread.
+    pub fn search<F: FnMut(&Iteration)>(&mut self, game: &Game, budget: Option<Duration>, report: F) -> Iteration {
+        let max_depth = self.max_depth;
+        let main = &mut self.main;
+        let helpers = &mut self.helpers;
+        let stops: Vec<_> = helpers.iter().map(|h| h.stop_handle()).collect();
+
+        // a helper that finished before the last search stopped it is still flagged,
+        // and would give up after its first iteration
+        for stop in &stops {
+            stop.store(false, Ordering::Relaxed);
+        }
+
+        let (result, helper_nodes) = thread::scope(|scope| {
+            let running: Vec<_> = helpers
+                .iter_mut()
+                .map(|helper| {
+                    helper.max_depth = max_depth;
+                    scope.spawn(move || helper.search(game, budget, |_| ()))
+                })
+                .collect();
+
+            main.max_depth = max_depth;
+            let result = main.search(game, budget, report);
+            for stop in &stops {
+                stop.store(true, Ordering::Relaxed);
+            }
+            let nodes = running.into_iter().map(|h| h.join().expect("helper search panicked").nodes).collect();
+            (result, nodes)
+        });
+
+        self.helper_nodes = helper_nodes;
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::eval::heuristic::Heuristic;
+    use crate::solvers::alphabeta::AlphaBeta;
+    use crate::solvers::random::{Random, Seed};
+
+    #[test]
+    fn solves_like_one_thread() {
+        let full = Random::new(Game::new(), Seed::from_string("smp".to_string())).next().unwrap();
+        let g = Game::from_transcript(full.transcript[..full.transcript.len() - 10].to_vec());
+
+        let expected = AlphaBeta::new(Heuristic::new(), 64).search(&g).score;
+        let mut smp = Smp::new(Heuristic::new(), 4, 4);
+        let result = smp.search(&g, None, |_| ());
+        assert_eq!(result.result.score, expected);
+    }
+
+    #[test]
+    fn helpers_search_every_time() {
+        let mut smp = Smp::new(Heuristic::new(), 3, 4);
+        smp.max_depth = 5;
+        let g = Game::from_transcript(crate::transcript::Transcript::from_string("F5D6C3"));
+        for _ in 0..2 {
+            smp.search(&g, None, |_| ());
+            assert_eq!(smp.helper_nodes.len(), 2);
+            assert!(smp.helper_nodes.iter().all(|&n| n > 0));
+        }
+
+        // a flag left over from an earlier search doesn't stop the next one
+        smp.helpers[0].stop_handle().store(true, Ordering::Relaxed);
+        smp.search(&g, None, |_| ());
+        assert!(smp.helper_nodes.iter().all(|&n| n > 0));
+    }
+
+    #[test]
+    fn respects_budget() {
+        let mut smp = Smp::new(Heuristic::new(), 3, 4);
+        let budget = Duration::from_millis(50);
+        let start = std::time::Instant::now();
+        let result = smp.search(&Game::new(), Some(budget), |_| ());
+
+        assert!(start.elapsed() < budget * 4);
+        assert!(result.result.best.is_some());
+    }
+}
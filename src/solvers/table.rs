@@ -0,0 +1,271 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use crate::board::{Board, MAX_SIZE};
+use crate::disk::Disk;
+use crate::game::Game;
+use crate::position::{Position, PositionState};
+use crate::rules::Supply;
+use crate::transcript::Transcript;
+
+// A transposition table shared between search threads without locks. Each entry
+// stores its key XORed with its data, so a torn write from two threads racing on
+// the same slot fails verification and reads as a miss.
+
+// squares are indexed as on the largest board, whatever the size being played
+const SQUARES: usize = MAX_SIZE * MAX_SIZE;
+
+// packed move values, beside square indices
+const PASS: u64 = 0xfffe;
+const NO_MOVE: u64 = 0xffff;
+// set in every packed entry, so an empty slot never reads as one
+const FILLED: u64 = 1 << 47;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Bound {
+    Exact,
+    // the score is at least this good
+    Lower,
+    // the score is at most this good
+    Upper,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Entry {
+    pub depth: usize,
+    pub score: i32,
+    pub bound: Bound,
+    pub best: Option<Transcript>,
+}
+
+impl Entry {
+    fn pack(&self) -> u64 {
+        let bound = match self.bound {
+            Bound::Exact => 0,
+            Bound::Lower => 1,
+            Bound::Upper => 2,
+        };
+        let best = match self.best {
+            None => NO_MOVE,
+            Some(Transcript::Pass) => PASS,
+            Some(Transcript::Play(p)) => (p.y * MAX_SIZE + p.x) as u64,
+        };
+
+        (self.score as u32 as u64) | (self.depth.min(255) as u64) << 32 | bound << 40 | FILLED | best << 48
+    }
+
+    fn unpack(data: u64) -> Self {
+        let bound = match (data >> 40) & 0x7f {
+            0 => Bound::Exact,
+            1 => Bound::Lower,
+            _ => Bound::Upper,
+        };
+        let best = match (data >> 48) & 0xffff {
+            NO_MOVE => None,
+            PASS => Some(Transcript::Pass),
+            index => Some(Transcript::Play(Position {
+                x: index as usize % MAX_SIZE,
+                y: index as usize / MAX_SIZE,
+            })),
+        };
+
+        Entry {
+            score: data as u32 as i32,
+            depth: ((data >> 32) & 0xff) as usize,
+            bound,
+            best,
+        }
+    }
+}
+
+struct Slot {
+    check: AtomicU64,
+    data: AtomicU64,
+}
+
+pub struct TranspositionTable {
+    slots: Vec<Slot>,
+    mask: usize,
+}
+
+impl TranspositionTable {
+    // sized to the largest power of two number of entries that fits
+    pub fn new(megabytes: usize) -> Self {
+        let wanted = (megabytes.max(1) << 20) / std::mem::size_of::<Slot>();
+        let size = 1usize << (usize::BITS - 1 - wanted.leading_zeros());
+        let slots = (0..size)
+            .map(|_| Slot {
+                check: AtomicU64::new(0),
+                data: AtomicU64::new(0),
+            })
+            .collect();
+
+        TranspositionTable { slots, mask: size - 1 }
+    }
+
+    pub fn probe(&self, key: u64) -> Option<Entry> {
+        let slot = &self.slots[key as usize & self.mask];
+        let data = slot.data.load(Ordering::Relaxed);
+        let check = slot.check.load(Ordering::Relaxed);
+
+        if data & FILLED != 0 && check ^ data == key {
+            Some(Entry::unpack(data))
+        } else {
+            None
+        }
+    }
+
+    // replaces whatever was in the slot unless it holds a deeper result for the same position
+    pub fn store(&self, key: u64, entry: Entry) {
+        let slot = &self.slots[key as usize & self.mask];
+        if let Some(existing) = self.probe(key) {
+            if existing.depth > entry.depth {
+                return;
+            }
+        }
+
+        let data = entry.pack();
+        slot.data.store(data, Ordering::Relaxed);
+        slot.check.store(key ^ data, Ordering::Relaxed);
+    }
+}
+
+// Zobrist keys: one random number per square and color, and one for light to move.
+const KEYS: [[u64; 2]; SQUARES] = zobrist_keys();
+const LIGHT_TO_MOVE: u64 = splitmix(SQUARES as u64 * 2 + 1);
+
+const fn splitmix(seed: u64) -> u64 {
+    let mut z = seed.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+const fn zobrist_keys() -> [[u64; 2]; SQUARES] {
+    let mut keys = [[0; 2]; SQUARES];
+    let mut i = 0;
+    while i < SQUARES {
+        keys[i][0] = splitmix(i as u64 * 2);
+        keys[i][1] = splitmix(i as u64 * 2 + 1);
+        i += 1;
+    }
+    keys
+}
+
+pub fn hash(board: &Board, turn: Disk) -> u64 {
+    let mut key = match turn {
+        Disk::Dark => 0,
+        Disk::Light => LIGHT_TO_MOVE,
+    };
+    for p in board.iter() {
+        let index = p.y * MAX_SIZE + p.x;
+        match board.get(p.x, p.y) {
+            // blocked squares are the same all game long
+            PositionState::Empty | PositionState::Blocked => continue,
+            PositionState::Dark => key ^= KEYS[index][0],
+            PositionState::Light => key ^= KEYS[index][1],
+        }
+    }
+    key
+}
+
+// The key for a game's position, which also depends on the board size and rules,
+// and on the discs each player has left when the rules limit them.
+pub fn game_hash(game: &Game) -> u64 {
+    let rules = game.rules();
+    let supply = match rules.supply {
+        Supply::Unlimited => 0,
+        Supply::Pass(_) => 1,
+        Supply::HandOver(_) => 2,
+    };
+    let variant = game.board().size() as u64 | (rules.anti as u64) << 8 | (rules.placement as u64) << 9 | supply << 10;
+    // inverted to keep clear of the seeds the square keys are made from
+    let key = hash(game.board(), game.turn) ^ splitmix(!variant);
+    match game.supply() {
+        None => key,
+        Some((dark, light)) => key ^ splitmix((SQUARES as u64 + dark as u64) << 32 | light as u64),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rules::Rules;
+
+    #[test]
+    fn packing() {
+        let entries = vec![
+            Entry { depth: 12, score: -6400, bound: Bound::Upper, best: None },
+            Entry { depth: 0, score: 37, bound: Bound::Exact, best: Some(Transcript::Pass) },
+            Entry {
+                depth: 60,
+                score: i32::MIN + 1,
+                bound: Bound::Lower,
+                best: Some(Transcript::Play(Position { x: 7, y: 6 })),
+            },
+            Entry { depth: 3, score: 0, bound: Bound::Exact, best: Some(Transcript::Play(Position { x: 15, y: 15 })) },
+        ];
+        for e in entries {
+            assert_eq!(Entry::unpack(e.pack()), e);
+        }
+    }
+
+    #[test]
+    fn store_and_probe() {
+        let table = TranspositionTable::new(1);
+        let g = Game::new();
+        let key = hash(g.board(), g.turn);
+        assert_eq!(table.probe(key), None);
+
+        let shallow = Entry { depth: 2, score: 5, bound: Bound::Exact, best: None };
+        let deep = Entry { depth: 6, score: 9, bound: Bound::Lower, best: None };
+        table.store(key, deep);
+        table.store(key, shallow);
+        assert_eq!(table.probe(key), Some(deep));
+
+        // a different position mapping to the same slot doesn't verify
+        assert_eq!(table.probe(key ^ ((table.mask as u64 + 1) << 1)), None);
+
+        // an entry that packs to nothing but its tag is still found
+        let zero = Entry { depth: 0, score: 0, bound: Bound::Exact, best: Some(Transcript::Play(Position { x: 0, y: 0 })) };
+        table.store(0, zero);
+        assert_eq!(table.probe(0), Some(zero));
+        assert_eq!(TranspositionTable::new(1).probe(0), None);
+    }
+
+    #[test]
+    fn supplies() {
+        let rules = Rules { supply: Supply::HandOver(30), ..Rules::new() };
+        let g = Game::with_rules(8, rules);
+        assert_eq!(hash(g.board(), g.turn), hash(Game::new().board(), g.turn));
+        assert_ne!(game_hash(&g), game_hash(&Game::new()));
+    }
+
+    #[test]
+    fn variants() {
+        // the same discs on a different size of board, or under different rules, are a different position
+        let standard = game_hash(&Game::new());
+        assert_ne!(game_hash(&Game::with_rules(8, Rules::anti())), standard);
+        assert_ne!(game_hash(&Game::with_size(10)), game_hash(&Game::with_size(12)));
+
+        let board = *Game::new().board();
+        let mut large = Board::with_size(10);
+        for p in board.iter() {
+            large.set(p.x, p.y, board.get(p.x, p.y));
+        }
+        let large = Game::from_layout(large, Rules::new());
+        assert_eq!(hash(large.board(), large.turn), hash(&board, Disk::Dark));
+        assert_ne!(game_hash(&large), standard);
+    }
+
+    #[test]
+    fn hashing() {
+        let g = Game::new();
+        assert_ne!(hash(g.board(), Disk::Dark), hash(g.board(), Disk::Light));
+
+        // transpositions hash the same
+        let a = Game::from_transcript(Transcript::from_string("F5F6E6F4E3"));
+        let b = Game::from_transcript(Transcript::from_string("E6F6F5F4E3"));
+        assert_eq!(a.board(), b.board());
+        assert_eq!(hash(a.board(), a.turn), hash(b.board(), b.turn));
+    }
+}
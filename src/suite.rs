@@ -0,0 +1,133 @@
+use std::collections::HashSet;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader};
+use std::path::Path;
+
+use rand::seq::SliceRandom;
+
+use crate::book;
+use crate::eval::Evaluator;
+use crate::game::Game;
+use crate::import;
+use crate::solvers::alphabeta::AlphaBeta;
+use crate::solvers::random::Seed;
+use crate::transcript::Transcript;
+
+// A set of starting positions for matches, so that deterministic players don't
+// replay the same game every time.
+pub struct Suite {
+    pub openings: Vec<Game>,
+}
+
+impl Suite {
+    // Reads one opening per line, like the XOT list of 8-move openings. Moves may be
+    // in either case and separated by spaces; blank lines and lines starting with #
+    // are skipped.
+    pub fn load<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let reader = BufReader::new(File::open(path)?);
+        let mut openings = Vec::new();
+
+        for line in reader.lines() {
+            let line = line?;
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let invalid = |e: String| io::Error::new(io::ErrorKind::InvalidData, format!("invalid opening {}: {}", line, e));
+            let imported = import::parse(line, &Game::new()).map_err(invalid)?;
+            openings.push(Game::new().try_replay(&imported.transcript, false).map_err(invalid)?);
+        }
+
+        Ok(Self { openings })
+    }
+
+    // Up to `count` different random openings of `plies` moves, keeping only those the
+    // searcher scores within `window` (in hundredths of a disc) of even.
+    pub fn balanced<E: Evaluator>(count: usize, plies: usize, searcher: &mut AlphaBeta<E>, window: i32, seed: Seed) -> Self {
+        let mut rng = seed.rng();
+        let mut seen = HashSet::new();
+        let mut openings = Vec::new();
+
+        // give up eventually if the window is too narrow to fill
+        for _ in 0..count * 100 {
+            if openings.len() == count {
+                break;
+            }
+
+            let mut game = Game::new();
+            while game.transcript.len() < plies {
+                let t = match game.legal_actions().as_slice() {
+                    [] => break,
+                    [Transcript::Pass] => Transcript::Pass,
+                    moves => *moves.choose(&mut rng).unwrap(),
+                };
+                game = game.apply(t).expect("legal actions can be applied");
+            }
+
+            if game.transcript.len() < plies || !seen.insert(book::key(&game)) {
+                continue;
+            }
+            if searcher.search(&game).score.abs() <= window {
+                openings.push(game);
+            }
+        }
+
+        Self { openings }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::contest::{Contest, FirstMove};
+    use crate::eval::heuristic::Heuristic;
+    use crate::eval::DISC;
+
+    #[test]
+    fn loads_xot_lines() {
+        let path = std::env::temp_dir().join(format!("reversi-suite-{}.txt", std::process::id()));
+        std::fs::write(&path, "# two openings\nf5f6e6f4e3c5c4e7\n\nF5 D6 C3 D3 C4 F4 C5 B3\n").unwrap();
+        let suite = Suite::load(&path).unwrap();
+
+        assert_eq!(suite.openings.len(), 2);
+        assert_eq!(Transcript::stringify(&suite.openings[0].transcript), "F5F6E6F4E3C5C4E7");
+        assert_eq!(suite.openings[1].transcript.len(), 8);
+
+        std::fs::write(&path, "F5F5\n").unwrap();
+        let result = Suite::load(&path);
+        assert_eq!(result.err().unwrap().kind(), io::ErrorKind::InvalidData);
+
+        // coordinates off the board are an error in the file too
+        std::fs::write(&path, "F5 Z9\n").unwrap();
+        let result = Suite::load(&path);
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(result.err().unwrap().kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn balanced_openings() {
+        let mut searcher = AlphaBeta::new(Heuristic::new(), 2);
+        let suite = Suite::balanced(5, 6, &mut searcher, 3 * DISC, Seed::from_string("balanced".to_string()));
+        assert_eq!(suite.openings.len(), 5);
+
+        let keys: HashSet<_> = suite.openings.iter().map(book::key).collect();
+        assert_eq!(keys.len(), 5);
+        for game in &suite.openings {
+            assert_eq!(game.transcript.len(), 6);
+            assert!(searcher.search(game).score.abs() <= 3 * DISC);
+        }
+    }
+
+    #[test]
+    fn contests_start_from_the_opening() {
+        let mut searcher = AlphaBeta::new(Heuristic::new(), 1);
+        let suite = Suite::balanced(1, 8, &mut searcher, 10 * DISC, Seed::from_string("contest".to_string()));
+        let opening = suite.openings[0].clone();
+
+        let mut contest = Contest::new(opening.clone(), Box::new(FirstMove), Box::new(FirstMove));
+        contest.play();
+        assert!(contest.game.transcript.starts_with(&opening.transcript));
+        assert!(contest.game.is_complete());
+    }
+}
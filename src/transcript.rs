@@ -1,5 +1,6 @@
 use std::fmt;
 
+use crate::board::MAX_SIZE;
 use crate::position::Position;
 
 pub const MANUBU_MARUO: &str = "E6F4E3F6G5D6E7F5C5";
@@ -24,16 +25,28 @@ impl fmt::Display for Transcript {
 }
 
 impl Transcript {
+    // Moves are a column letter and a row number, which takes two digits on boards
+    // larger than 9x9 ("J10"). P followed by anything but a digit ("PP", "PA") is a pass.
     pub fn from_string(source: &str) -> Vec<Self> {
         let mut output = Vec::new();
 
-        let mut chars = source.chars();
+        let mut chars = source.chars().peekable();
 
         while let Some(raw_x) = chars.next() {
-            if let Some(raw_y) = chars.next() {
-                let t = Transcript::from_chars(raw_x, raw_y);
-                output.push(t)
+            let Some(raw_y) = chars.next() else { break };
+            if raw_x.eq_ignore_ascii_case(&'P') && !raw_y.is_ascii_digit() {
+                output.push(Transcript::Pass);
+                continue;
             }
+
+            let mut digits = raw_y.to_string();
+            while let Some(c) = chars.next_if(|c| c.is_ascii_digit()) {
+                digits.push(c);
+            }
+            output.push(Transcript::Play(Position {
+                x: Transcript::char_to_x(raw_x.to_ascii_uppercase()),
+                y: Transcript::digits_to_y(&digits),
+            }));
         }
 
         output
@@ -45,7 +58,7 @@ impl Transcript {
             c => Transcript::char_to_x(c),
         };
 
-        let y = Transcript::char_to_y(raw_y);
+        let y = Transcript::digits_to_y(&raw_y.to_string());
 
         let position = Position { x, y };
         Transcript::Play(position)
@@ -59,23 +72,24 @@ impl Transcript {
         output
     }
 
-    pub fn symmetrical(original: Vec<Transcript>) -> Vec<Vec<Transcript>> {
+    // the game's images on a board `size` squares across
+    pub fn symmetrical(original: Vec<Transcript>, size: usize) -> Vec<Vec<Transcript>> {
         // first position is rotated.
-        let first = Transcript::transform(original.clone(), Position::rotate);
+        let first = Transcript::transform(original.clone(), Position::rotate, size);
         // second position is flipped.
-        let second = Transcript::transform(original.clone(), Position::flip);
+        let second = Transcript::transform(original.clone(), Position::flip, size);
         // third position is rotated and flipped.
-        let third = Transcript::transform(first.clone(), Position::flip);
+        let third = Transcript::transform(first.clone(), Position::flip, size);
 
         vec![first, second, third, original]
     }
 
-    fn transform(transcripts: Vec<Transcript>, f: fn(Position) -> Position) -> Vec<Self> {
+    fn transform(transcripts: Vec<Transcript>, f: fn(Position, usize) -> Position, size: usize) -> Vec<Self> {
         let mut output = Vec::new();
         for t in transcripts {
             let new_t = match t {
                 Transcript::Pass => Transcript::Pass,
-                Transcript::Play(p) => Transcript::Play(f(p)),
+                Transcript::Play(p) => Transcript::Play(f(p, size)),
             };
             output.push(new_t);
         }
@@ -85,36 +99,22 @@ impl Transcript {
 
     fn char_to_x(c: char) -> usize {
         match c {
-            'A' => 0,
-            'B' => 1,
-            'C' => 2,
-            'D' => 3,
-            'E' => 4,
-            'F' => 5,
-            'G' => 6,
-            'H' => 7,
-            _ => panic!("Invalid X {}; must be A..H", c),
+            'A'..='P' => c as usize - 'A' as usize,
+            _ => panic!("Invalid X {}; must be A..P", c),
         }
     }
 
-    fn char_to_y(c: char) -> usize {
-        match c.to_digit(10) {
-            None => panic!("Invalid Y {}; must be 1..8", c),
-            Some(y) => (y - 1) as usize,
+    fn digits_to_y(digits: &str) -> usize {
+        match digits.parse::<usize>() {
+            Ok(y) if (1..=MAX_SIZE).contains(&y) => y - 1,
+            _ => panic!("Invalid Y {}; must be 1..{}", digits, MAX_SIZE),
         }
     }
 
     fn x_to_char(x: usize) -> char {
         match x {
-            0 => 'A',
-            1 => 'B',
-            2 => 'C',
-            3 => 'D',
-            4 => 'E',
-            5 => 'F',
-            6 => 'G',
-            7 => 'H',
-            _ => panic!("Invalid X {}; must be 0..7", x),
+            0..MAX_SIZE => (b'A' + x as u8) as char,
+            _ => panic!("Invalid X {}; must be 0..{}", x, MAX_SIZE - 1),
         }
     }
 }
@@ -149,13 +149,35 @@ mod tests {
         let transcript_vec = Transcript::from_string(&transcript_source);
 
         // two flips should return to original
-        let flip_one = Transcript::transform(transcript_vec.clone(), Position::flip);
-        let flip_two = Transcript::transform(flip_one.clone(), Position::flip);
+        let flip_one = Transcript::transform(transcript_vec.clone(), Position::flip, 8);
+        let flip_two = Transcript::transform(flip_one.clone(), Position::flip, 8);
         assert_eq!(transcript_vec, flip_two);
 
         // two rotates should return to original
-        let rotate_one = Transcript::transform(transcript_vec.clone(), Position::rotate);
-        let rotate_two = Transcript::transform(rotate_one.clone(), Position::rotate);
+        let rotate_one = Transcript::transform(transcript_vec.clone(), Position::rotate, 8);
+        let rotate_two = Transcript::transform(rotate_one.clone(), Position::rotate, 8);
         assert_eq!(transcript_vec, rotate_two);
     }
+
+    #[test]
+    fn large_boards() {
+        // two-digit rows, column P and passes side by side
+        let moves = Transcript::from_string("J10A1P16PPe12");
+        assert_eq!(
+            moves,
+            vec![
+                Transcript::Play(Position { x: 9, y: 9 }),
+                Transcript::Play(Position { x: 0, y: 0 }),
+                Transcript::Play(Position { x: 15, y: 15 }),
+                Transcript::Pass,
+                Transcript::Play(Position { x: 4, y: 11 }),
+            ]
+        );
+        assert_eq!(Transcript::stringify(&moves), "J10A1P16PPE12");
+        assert_eq!(Transcript::from_string("F5PAd6"), Transcript::from_string("F5PPD6"));
+
+        // flipping on a 10x10 board maps J10 onto A1
+        let flipped = Transcript::transform(moves[..1].to_vec(), Position::flip, 10);
+        assert_eq!(flipped, moves[1..2].to_vec());
+    }
 }
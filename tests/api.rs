@@ -0,0 +1,77 @@
+// Uses the library only through its public API, the way other programs would.
+
+use std::time::Duration;
+
+use reversi::book::Book;
+use reversi::clock::Clock;
+use reversi::contest::{Engine, FirstMove};
+use reversi::eval::heuristic::{Heuristic, Parity};
+use reversi::eval::DISC;
+use reversi::external::External;
+use reversi::solvers::alphabeta::AlphaBeta;
+use reversi::solvers::incremental::Incremental;
+use reversi::solvers::random::{Random, Seed};
+use reversi::transcript::MANUBU_MARUO;
+use reversi::{Contest, Disk, Game, Position, Transcript};
+
+#[test]
+fn replays_transcripts() {
+    let game = Game::from_transcript(Transcript::from_string(MANUBU_MARUO));
+    assert!(game.is_complete());
+    assert_eq!(game.score(), (13, 0));
+    assert_eq!(Transcript::stringify(&game.transcript), MANUBU_MARUO);
+
+    let start = Game::new();
+    assert_eq!(start.turn, Disk::Dark);
+    let moves: Vec<Position> = start.valid_moves().into_iter().map(|vm| vm.position).collect();
+    assert_eq!(moves.len(), 4);
+    assert!(moves.contains(&Position { x: 5, y: 4 }));
+}
+
+#[test]
+fn generates_games() {
+    let first = Incremental::new(&Game::new()).next().unwrap();
+    assert!(first.is_complete());
+
+    // the same seed always plays the same game
+    let a = Random::new(Game::new(), Seed::from_string("api".to_string())).next().unwrap();
+    let b = Random::new(Game::new(), Seed::from_string("api".to_string())).next().unwrap();
+    assert!(a.is_complete());
+    assert_eq!(a.transcript, b.transcript);
+}
+
+#[test]
+fn searches() {
+    let game = Game::from_transcript(Transcript::from_string("E6F4E3F6G5D6E7F5"));
+    let result = AlphaBeta::new(Parity, 3).search(&game);
+    assert_eq!(result.best, Some(Transcript::from_string("C5")[0]));
+    assert_eq!(result.score, 13 * DISC);
+}
+
+#[test]
+fn plays_contests() {
+    let mut engine = Engine::new(Heuristic::new());
+    engine.deepening.max_depth = 2;
+
+    let mut contest = Contest::new(Game::new(), Box::new(engine), Box::new(FirstMove));
+    contest.set_clock(Clock::new(Duration::from_secs(30), Duration::from_secs(0)));
+    contest.play();
+    assert!(contest.game.is_complete());
+    assert_eq!(contest.forfeit, None);
+
+    let mut book = Book::new();
+    book.add_game(&contest.game, 8);
+    assert_eq!(book.len(), 9);
+    assert_eq!(book.lookup(&Game::new()).len(), 4);
+}
+
+#[test]
+fn plays_the_binary_as_an_external_engine() {
+    let binary = env!("CARGO_BIN_EXE_reversi");
+    let engine = External::spawn(binary, &["nboard", "--evaluator", "parity"], 2).unwrap();
+
+    let mut contest = Contest::new(Game::new(), Box::new(FirstMove), Box::new(engine));
+    contest.play();
+    assert_eq!(contest.forfeit_reason, None);
+    assert!(contest.game.is_complete());
+}